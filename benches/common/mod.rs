@@ -0,0 +1,43 @@
+//! A synthetic cache shared by the benchmarks
+use std::sync::Arc;
+
+use fuzzle::state::Provider;
+use fuzzle::SearchResult;
+
+pub const ENTRIES: usize = 50_000;
+/// As many matches as the default config keeps
+pub const MAX_RESULTS: usize = 1000;
+pub const WORDS: &[&str] = &[
+    "fire", "fox", "term", "code", "vim", "edit", "note", "pad", "music", "player", "image",
+    "view", "files", "manager", "mail", "web", "browser", "calc", "office", "writer",
+];
+
+/// Entries with made up names, a few of them desktop entries
+pub fn synthetic_cache(entries: usize) -> Arc<Vec<SearchResult>> {
+    let entries = (0..entries)
+        .map(|i| {
+            let name = format!(
+                "{}{}-{}",
+                WORDS[i % WORDS.len()],
+                WORDS[(i / WORDS.len()) % WORDS.len()],
+                i
+            );
+            let (provider, desktop_entry_path) = if i % 10 == 0 {
+                let path = format!("/usr/share/applications/{}.desktop", name);
+                (Provider::DesktopEntry, Some(path))
+            } else {
+                (Provider::Binary, None)
+            };
+            SearchResult {
+                desktop_entry_path,
+                ..SearchResult::new(
+                    provider,
+                    name.clone(),
+                    format!("Synthetic entry number {}", i),
+                    format!("/usr/bin/{}", name),
+                )
+            }
+        })
+        .collect();
+    Arc::new(entries)
+}
@@ -0,0 +1,48 @@
+//! Typing latency against a large cache.
+//!
+//! Run with `cargo bench`. Every keystroke should be well under a
+//! frame (16ms), both when narrowing and on a full scan.
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use fuzzle::search::{SearchIndex, Searcher};
+
+mod common;
+use common::{synthetic_cache, ENTRIES, MAX_RESULTS};
+
+fn keystrokes(c: &mut Criterion) {
+    let index = Arc::new(SearchIndex::new(synthetic_cache(ENTRIES)));
+    let query = "firefox";
+
+    c.bench_function("index 50k entries", |b| {
+        let cache = index.entries.clone();
+        b.iter(|| SearchIndex::new(cache.clone()))
+    });
+
+    for len in 1..=query.len() {
+        let typed = &query[..len];
+        c.bench_function(&format!("full scan '{}'", typed), |b| {
+            b.iter_batched(
+                || Searcher::new(index.clone(), MAX_RESULTS),
+                |mut searcher| searcher.search(typed, || false),
+                BatchSize::SmallInput,
+            )
+        });
+        c.bench_function(&format!("narrow '{}'", typed), |b| {
+            b.iter_batched(
+                || {
+                    // The state after typing everything before this keystroke
+                    let mut searcher = Searcher::new(index.clone(), MAX_RESULTS);
+                    searcher.search(&query[..len - 1], || false);
+                    searcher
+                },
+                |mut searcher| searcher.search(typed, || false),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, keystrokes);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+//! Entries scored per second, by query length, on a full scan.
+//!
+//! Longer queries are more work per entry, but fail to match sooner.
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use fuzzle::search::{SearchIndex, Searcher};
+
+mod common;
+use common::{synthetic_cache, ENTRIES, MAX_RESULTS};
+
+/// Typed one character at a time, the prefixes cover lengths 1 to 12
+const QUERIES: &[&str] = &["filesmanager", "webbrowser-1", "zzzzzzzzzzzz"];
+
+fn throughput(c: &mut Criterion) {
+    for &entries in &[ENTRIES, 4 * ENTRIES] {
+        let index = Arc::new(SearchIndex::new(synthetic_cache(entries)));
+        let mut group = c.benchmark_group(format!("throughput {} entries", entries));
+        group.throughput(Throughput::Elements(entries as u64));
+        for query in QUERIES {
+            for len in 1..=query.len() {
+                let typed = &query[..len];
+                group.bench_with_input(BenchmarkId::new(*query, len), typed, |b, typed| {
+                    // A new searcher each time, so nothing is narrowed
+                    b.iter(|| Searcher::new(index.clone(), MAX_RESULTS).search(typed, || false))
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, throughput);
+criterion_main!(benches);
@@ -0,0 +1,392 @@
+//! Key chords and the actions they trigger.
+use druid::{KeyCode, KeyEvent};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Everything that can be bound to a key chord
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Next,
+    Prev,
+    PageDown,
+    PageUp,
+    First,
+    Last,
+    Activate,
+    ActivateAlt,
+    DeleteWord,
+    Clear,
+    LineStart,
+    LineEnd,
+    Copy,
+    Cut,
+    Paste,
+    Quit,
+    CopyCommand,
+    CycleMatchMode,
+    Actions,
+    Complete,
+    CompletePath,
+}
+
+impl Action {
+    /// The name used for this action in the `[keys]` section of the config
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Next => "next",
+            Action::Prev => "prev",
+            Action::PageDown => "page-down",
+            Action::PageUp => "page-up",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::Activate => "activate",
+            Action::ActivateAlt => "activate-alt",
+            Action::DeleteWord => "delete-word",
+            Action::Clear => "clear",
+            Action::LineStart => "line-start",
+            Action::LineEnd => "line-end",
+            Action::Copy => "copy",
+            Action::Cut => "cut",
+            Action::Paste => "paste",
+            Action::Quit => "quit",
+            Action::CopyCommand => "copy-command",
+            Action::CycleMatchMode => "cycle-match-mode",
+            Action::Actions => "actions",
+            Action::Complete => "complete",
+            Action::CompletePath => "complete-path",
+        }
+    }
+}
+
+/// A key plus the modifiers that must be held, like `ctrl+shift+c`
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyChord {
+    key: KeyCode,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    meta: bool,
+}
+
+impl KeyChord {
+    pub fn parse(chord: &str) -> Result<Self, String> {
+        let trimmed = chord.trim();
+        // The key comes last, and can be `+` itself, like in `ctrl++`
+        let (modifiers, key_name) = match trimmed.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => {
+                (&rest[..rest.len().saturating_sub(1)], "+")
+            }
+            _ => match trimmed.rfind('+') {
+                Some(i) => (&trimmed[..i], trimmed[i + 1..].trim()),
+                None => ("", trimmed),
+            },
+        };
+        // `+` is a shifted `=`, like on US keyboards
+        let (key, shift) = match key_name {
+            "+" => (KeyCode::Equals, true),
+            "" => return Err(format!("no key in \"{}\"", chord)),
+            _ => match key_code(&key_name.to_lowercase()) {
+                Some(key) => (key, false),
+                None => return Err(format!("unknown key \"{}\" in \"{}\"", key_name, chord)),
+            },
+        };
+        let mut result = Self {
+            key,
+            ctrl: false,
+            alt: false,
+            shift,
+            meta: false,
+        };
+        let modifiers = if modifiers.is_empty() {
+            vec![]
+        } else {
+            modifiers.split('+').map(str::trim).collect()
+        };
+        for modifier in modifiers {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => result.ctrl = true,
+                "alt" => result.alt = true,
+                "shift" => result.shift = true,
+                "meta" | "super" | "logo" => result.meta = true,
+                _ => {
+                    return Err(format!(
+                        "unknown modifier \"{}\" in \"{}\"",
+                        modifier, chord
+                    ))
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        event.key_code == self.key
+            && event.mods.ctrl == self.ctrl
+            && event.mods.alt == self.alt
+            && event.mods.shift == self.shift
+            && event.mods.meta == self.meta
+    }
+}
+
+/// Map key chords to actions.
+///
+/// Bindings set in the `[keys]` section of the config replace the default
+/// bindings of that action, every other action keeps its defaults.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "HashMap<String, Vec<String>>")]
+pub struct Keymap {
+    bindings: Vec<(KeyChord, Action)>,
+}
+
+impl Keymap {
+    /// Every action bound to this key event, in order: the first one that
+    /// applies wins
+    pub fn actions(&self, event: &KeyEvent) -> Vec<Action> {
+        self.bindings
+            .iter()
+            .filter(|(chord, _)| chord.matches(event))
+            .map(|(_, action)| *action)
+            .collect()
+    }
+
+    fn default_bindings() -> Vec<(Action, Vec<&'static str>)> {
+        vec![
+            // Before next, which tab does when there's no path to complete
+            (Action::CompletePath, vec!["tab"]),
+            (Action::Next, vec!["ctrl+j", "ctrl+n", "tab", "down"]),
+            (Action::Prev, vec!["ctrl+k", "ctrl+p", "shift+tab", "up"]),
+            (Action::PageDown, vec!["pagedown"]),
+            (Action::PageUp, vec!["pageup"]),
+            (Action::First, vec!["ctrl+home"]),
+            (Action::Last, vec!["ctrl+end"]),
+            (Action::Activate, vec!["return"]),
+            (Action::ActivateAlt, vec!["shift+return"]),
+            (Action::DeleteWord, vec!["ctrl+w", "ctrl+backspace"]),
+            (Action::Clear, vec!["ctrl+u"]),
+            (Action::LineStart, vec!["ctrl+a", "home"]),
+            (Action::LineEnd, vec!["ctrl+e", "end"]),
+            (Action::Copy, vec!["ctrl+c"]),
+            (Action::Cut, vec!["ctrl+x"]),
+            (Action::Paste, vec!["ctrl+v", "shift+insert"]),
+            (Action::Quit, vec!["escape"]),
+            (Action::CopyCommand, vec!["ctrl+shift+c"]),
+            (Action::CycleMatchMode, vec!["alt+m"]),
+            (Action::Actions, vec!["ctrl+o"]),
+            (Action::Complete, vec!["ctrl+space"]),
+        ]
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::try_from(HashMap::new()).unwrap()
+    }
+}
+
+impl TryFrom<HashMap<String, Vec<String>>> for Keymap {
+    type Error = String;
+
+    fn try_from(custom: HashMap<String, Vec<String>>) -> Result<Self, Self::Error> {
+        let default_bindings = Self::default_bindings();
+        if let Some(name) = custom
+            .keys()
+            .find(|name| !default_bindings.iter().any(|(a, _)| a.name() == *name))
+        {
+            return Err(format!("unknown action \"{}\" in [keys]", name));
+        }
+
+        let mut bindings = Vec::new();
+        let mut defaults = Vec::new();
+        for (action, default_chords) in default_bindings {
+            match custom.get(action.name()) {
+                Some(chords) => {
+                    for chord in chords {
+                        bindings.push((KeyChord::parse(chord)?, action));
+                    }
+                }
+                None => {
+                    for chord in default_chords {
+                        defaults.push((KeyChord::parse(chord)?, action));
+                    }
+                }
+            }
+        }
+        // Custom bindings go first, so they win over a default bound to the same chord
+        bindings.append(&mut defaults);
+        Ok(Self { bindings })
+    }
+}
+
+/// Translate a key name, as written in the config, to a druid KeyCode
+fn key_code(name: &str) -> Option<KeyCode> {
+    let key = match name {
+        "a" => KeyCode::KeyA,
+        "b" => KeyCode::KeyB,
+        "c" => KeyCode::KeyC,
+        "d" => KeyCode::KeyD,
+        "e" => KeyCode::KeyE,
+        "f" => KeyCode::KeyF,
+        "g" => KeyCode::KeyG,
+        "h" => KeyCode::KeyH,
+        "i" => KeyCode::KeyI,
+        "j" => KeyCode::KeyJ,
+        "k" => KeyCode::KeyK,
+        "l" => KeyCode::KeyL,
+        "m" => KeyCode::KeyM,
+        "n" => KeyCode::KeyN,
+        "o" => KeyCode::KeyO,
+        "p" => KeyCode::KeyP,
+        "q" => KeyCode::KeyQ,
+        "r" => KeyCode::KeyR,
+        "s" => KeyCode::KeyS,
+        "t" => KeyCode::KeyT,
+        "u" => KeyCode::KeyU,
+        "v" => KeyCode::KeyV,
+        "w" => KeyCode::KeyW,
+        "x" => KeyCode::KeyX,
+        "y" => KeyCode::KeyY,
+        "z" => KeyCode::KeyZ,
+        "0" => KeyCode::Key0,
+        "1" => KeyCode::Key1,
+        "2" => KeyCode::Key2,
+        "3" => KeyCode::Key3,
+        "4" => KeyCode::Key4,
+        "5" => KeyCode::Key5,
+        "6" => KeyCode::Key6,
+        "7" => KeyCode::Key7,
+        "8" => KeyCode::Key8,
+        "9" => KeyCode::Key9,
+        "f1" => KeyCode::F1,
+        "f2" => KeyCode::F2,
+        "f3" => KeyCode::F3,
+        "f4" => KeyCode::F4,
+        "f5" => KeyCode::F5,
+        "f6" => KeyCode::F6,
+        "f7" => KeyCode::F7,
+        "f8" => KeyCode::F8,
+        "f9" => KeyCode::F9,
+        "f10" => KeyCode::F10,
+        "f11" => KeyCode::F11,
+        "f12" => KeyCode::F12,
+        "`" => KeyCode::Backtick,
+        "-" => KeyCode::Minus,
+        "=" => KeyCode::Equals,
+        "[" => KeyCode::LeftBracket,
+        "]" => KeyCode::RightBracket,
+        ";" => KeyCode::Semicolon,
+        "'" => KeyCode::Quote,
+        "\\" => KeyCode::Backslash,
+        "," => KeyCode::Comma,
+        "." => KeyCode::Period,
+        "/" => KeyCode::Slash,
+        "space" => KeyCode::Space,
+        "tab" => KeyCode::Tab,
+        "return" | "enter" => KeyCode::Return,
+        "escape" | "esc" => KeyCode::Escape,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" | "pgup" => KeyCode::PageUp,
+        "pagedown" | "pgdn" => KeyCode::PageDown,
+        "up" => KeyCode::ArrowUp,
+        "down" => KeyCode::ArrowDown,
+        "left" => KeyCode::ArrowLeft,
+        "right" => KeyCode::ArrowRight,
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use druid::KeyModifiers;
+
+    fn chord(key: KeyCode, ctrl: bool, shift: bool) -> KeyChord {
+        KeyChord {
+            key,
+            ctrl,
+            alt: false,
+            shift,
+            meta: false,
+        }
+    }
+
+    #[test]
+    fn parse_modifiers_in_any_case() {
+        assert_eq!(
+            KeyChord::parse("ctrl+shift+c"),
+            Ok(chord(KeyCode::KeyC, true, true))
+        );
+        assert_eq!(
+            KeyChord::parse(" Control + Return "),
+            Ok(chord(KeyCode::Return, true, false))
+        );
+        assert_eq!(
+            KeyChord::parse("tab"),
+            Ok(chord(KeyCode::Tab, false, false))
+        );
+    }
+
+    #[test]
+    fn parse_plus_as_the_key() {
+        assert_eq!(
+            KeyChord::parse("ctrl++"),
+            Ok(chord(KeyCode::Equals, true, true))
+        );
+        assert_eq!(
+            KeyChord::parse("+"),
+            Ok(chord(KeyCode::Equals, false, true))
+        );
+    }
+
+    #[test]
+    fn parse_errors_name_what_is_wrong() {
+        assert_eq!(
+            KeyChord::parse("ctrl+"),
+            Err("no key in \"ctrl+\"".to_string())
+        );
+        assert_eq!(
+            KeyChord::parse("ctrl+nope"),
+            Err("unknown key \"nope\" in \"ctrl+nope\"".to_string())
+        );
+        assert_eq!(
+            KeyChord::parse("hyper+a"),
+            Err("unknown modifier \"hyper\" in \"hyper+a\"".to_string())
+        );
+    }
+
+    #[test]
+    fn custom_bindings_replace_the_defaults_of_their_action() {
+        let mut custom = HashMap::new();
+        custom.insert("next".to_string(), vec!["ctrl+l".to_string()]);
+        let keymap = Keymap::try_from(custom).unwrap();
+        let ctrl = KeyModifiers {
+            ctrl: true,
+            ..KeyModifiers::default()
+        };
+        let ctrl_l = KeyEvent::new(KeyCode::KeyL, false, ctrl, "l", "l");
+        assert_eq!(keymap.actions(&ctrl_l), vec![Action::Next]);
+        let down = KeyEvent::new(KeyCode::ArrowDown, false, KeyModifiers::default(), "", "");
+        assert_eq!(keymap.actions(&down), vec![]);
+
+        let mut custom = HashMap::new();
+        custom.insert("nowhere".to_string(), vec!["ctrl+l".to_string()]);
+        assert_eq!(
+            Keymap::try_from(custom).unwrap_err(),
+            "unknown action \"nowhere\" in [keys]"
+        );
+    }
+
+    #[test]
+    fn tab_completes_paths_before_moving_down() {
+        let tab = KeyEvent::new(KeyCode::Tab, false, KeyModifiers::default(), "\t", "\t");
+        assert_eq!(
+            Keymap::default().actions(&tab),
+            vec![Action::CompletePath, Action::Next]
+        );
+    }
+}
@@ -0,0 +1,459 @@
+//! User configuration, read from `$XDG_CONFIG_HOME/fuzzle/config.toml`.
+//!
+//! Every section and every key is optional, anything missing falls back
+//! to the defaults below.
+use druid::Env;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use xdg::BaseDirectories;
+
+use crate::launch::exec_quote;
+use crate::search::{CaseMatching, MatchMode};
+
+mod theme;
+pub use theme::*;
+
+mod keymap;
+pub use keymap::{Action, Keymap};
+
+mod watch;
+pub use watch::{watch_theme, THEME_CHANGED};
+
+const CONFIG_FILE: &str = "config.toml";
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Name of a theme file in `$XDG_CONFIG_HOME/fuzzle/themes/`, without extension.
+    /// When set, it replaces the `[theme]` section.
+    pub theme_name: Option<String>,
+    pub theme: Theme,
+    pub window: WindowConfig,
+    pub behavior: BehaviorConfig,
+    pub keys: Keymap,
+    pub providers: ProvidersConfig,
+    pub launch: LaunchConfig,
+    /// Entries of the user's own, from the `[[entries]]` sections
+    pub entries: Vec<CustomEntry>,
+    /// Short queries that go straight to a result, found by name or by desktop file name
+    pub aliases: HashMap<String, String>,
+    pub web: WebConfig,
+    pub clipboard: ClipboardConfig,
+    pub ssh: SshConfig,
+    pub session: SessionConfig,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub width: f64,
+    pub height: f64,
+    pub visible_rows: usize,
+    /// Show what the selected result runs, and where it comes from
+    pub show_details: bool,
+    /// Show a preview of the selected result right of the list
+    pub preview: bool,
+    /// The window gets this much wider when the preview is shown
+    pub preview_width: f64,
+    /// Moved to `[theme]`, still read from here with a warning
+    pub row_height: Option<f64>,
+    pub padding: Option<f64>,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 550.,
+            height: 320.,
+            visible_rows: 3,
+            show_details: true,
+            preview: false,
+            preview_width: 300.,
+            row_height: None,
+            padding: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BehaviorConfig {
+    /// Launch results with a single click instead of a double click
+    pub click_to_launch: bool,
+    /// Wait this long after the last keystroke before searching
+    pub search_debounce_ms: u64,
+    /// Keep only this many of the best matches
+    pub max_results: usize,
+    /// How queries are matched, the `cycle-match-mode` key switches it
+    pub match_mode: MatchMode,
+    /// Understand skim's 'exact ^prefix suffix$ !not and | in queries
+    pub extended_syntax: bool,
+    /// Accents are always ignored, case depends on this
+    pub case_matching: CaseMatching,
+}
+
+impl Default for BehaviorConfig {
+    fn default() -> Self {
+        Self {
+            click_to_launch: false,
+            search_debounce_ms: 15,
+            max_results: 1000,
+            match_mode: MatchMode::Fuzzy,
+            extended_syntax: true,
+            case_matching: CaseMatching::Smart,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LaunchConfig {
+    /// Runs the command that follows it in a new terminal window
+    pub terminal: String,
+}
+
+impl Default for LaunchConfig {
+    fn default() -> Self {
+        Self {
+            terminal: "xterm -e".to_string(),
+        }
+    }
+}
+
+/// A result defined in the config, that runs a command or opens a URL
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CustomEntry {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// An icon name from the icon theme, or the path of an image
+    pub icon: Option<String>,
+    pub command: Option<String>,
+    pub url: Option<String>,
+}
+
+impl CustomEntry {
+    /// The `Exec`-like command line of the entry
+    pub fn command_line(&self) -> String {
+        match (&self.command, &self.url) {
+            (Some(command), _) => command.clone(),
+            (None, Some(url)) => format!("xdg-open {}", exec_quote(url)),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Web searches: `<keyword> <terms>` opens the URL of the engine with
+/// that keyword in the browser
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebConfig {
+    /// Keyword of the engine offered when nothing matches, empty for none
+    pub fallback: String,
+    /// Engines set here are added to the default ones, and replace
+    /// those with the same keyword
+    #[serde(deserialize_with = "engines_over_defaults")]
+    pub engines: HashMap<String, SearchEngine>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchEngine {
+    pub name: String,
+    /// `{}` is replaced with the search terms
+    pub url: String,
+}
+
+impl Default for WebConfig {
+    fn default() -> Self {
+        let engines = [
+            ("g", "Google", "https://www.google.com/search?q={}"),
+            ("gh", "GitHub", "https://github.com/search?q={}"),
+            ("crates", "crates.io", "https://crates.io/search?q={}"),
+        ];
+        Self {
+            fallback: "g".to_string(),
+            engines: engines
+                .iter()
+                .map(|(keyword, name, url)| {
+                    let engine = SearchEngine {
+                        name: name.to_string(),
+                        url: url.to_string(),
+                    };
+                    (keyword.to_string(), engine)
+                })
+                .collect(),
+        }
+    }
+}
+
+fn engines_over_defaults<'de, D>(deserializer: D) -> Result<HashMap<String, SearchEngine>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut engines = WebConfig::default().engines;
+    engines.extend(HashMap::<String, SearchEngine>::deserialize(deserializer)?);
+    Ok(engines)
+}
+
+/// The clipboard history, recorded by `fuzzle --clip-daemon`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClipboardConfig {
+    /// Older entries are forgotten
+    pub max_entries: usize,
+    /// Bigger copies are not recorded, in bytes
+    pub max_size: usize,
+    /// How often the daemon looks at the clipboard
+    pub poll_interval_ms: u64,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 200,
+            max_size: 64 * 1024,
+            poll_interval_ms: 500,
+        }
+    }
+}
+
+/// Hosts are read from `~/.ssh/config`, and from `~/.ssh/known_hosts` if asked
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SshConfig {
+    pub known_hosts: bool,
+}
+
+impl Default for SshConfig {
+    fn default() -> Self {
+        Self { known_hosts: true }
+    }
+}
+
+/// Commands of the session actions, an empty one leaves the action out
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionConfig {
+    pub lock: String,
+    pub logout: String,
+    pub suspend: String,
+    pub hibernate: String,
+    pub reboot: String,
+    pub power_off: String,
+    /// Ask before logging out, hibernating, rebooting or powering off
+    pub confirm: bool,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            lock: "loginctl lock-session".to_string(),
+            logout: "loginctl terminate-session self".to_string(),
+            suspend: "systemctl suspend".to_string(),
+            hibernate: "systemctl hibernate".to_string(),
+            reboot: "systemctl reboot".to_string(),
+            power_off: "systemctl poweroff".to_string(),
+            confirm: true,
+        }
+    }
+}
+
+/// Which sources of results are enabled
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProvidersConfig {
+    pub desktop_entries: bool,
+    pub binaries: bool,
+    /// Run the query as a shell command, when it starts with `>` or `!`
+    /// or when nothing matches it
+    pub commands: bool,
+    /// Search engine shortcuts, see `WebConfig`
+    pub web: bool,
+    /// Open URLs and paths typed in the query
+    pub locations: bool,
+    /// Convert units, bases and dates, and do arithmetic
+    pub converter: bool,
+    /// Search the clipboard history with `clip`, see `ClipboardConfig`
+    pub clipboard: bool,
+    /// Emoji and symbols, by `:shortcode`, `u+` code point or name
+    pub characters: bool,
+    /// Hosts to connect to with `ssh`, see `SshConfig`
+    pub ssh: bool,
+    /// Lock, log out, suspend, hibernate, reboot and power off, see `SessionConfig`
+    pub session: bool,
+}
+
+impl Default for ProvidersConfig {
+    fn default() -> Self {
+        Self {
+            desktop_entries: true,
+            binaries: true,
+            commands: true,
+            web: true,
+            locations: true,
+            converter: true,
+            clipboard: false,
+            characters: true,
+            ssh: true,
+            session: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, err) => write!(f, "can't read {}: {}", path.display(), err),
+            ConfigError::Parse(path, err) => write!(f, "error in {}: {}", path.display(), err),
+            ConfigError::Invalid(path, msg) => write!(f, "error in {}: {}", path.display(), msg),
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration file, if there is one.
+    /// A missing file is not an error, it just means defaults.
+    pub fn load() -> Result<Self, ConfigError> {
+        match config_path() {
+            Some(path) => Self::load_from(path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: PathBuf) -> Result<Self, ConfigError> {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => return Err(ConfigError::Read(path, err)),
+        };
+        let mut config: Config = match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(err) => return Err(ConfigError::Parse(path, err)),
+        };
+        config.migrate();
+        if let Some(theme_path) = config.theme_path() {
+            config.theme = match Theme::load_file(&theme_path) {
+                Ok(theme) => theme,
+                Err(msg) => return Err(ConfigError::Invalid(theme_path, msg)),
+            };
+        } else if let Some(name) = &config.theme_name {
+            let msg = format!("theme \"{}\" not found in the themes directory", name);
+            return Err(ConfigError::Invalid(path, msg));
+        }
+        match config.validate() {
+            Ok(()) => Ok(config),
+            Err(msg) => Err(ConfigError::Invalid(path, msg)),
+        }
+    }
+
+    /// Move keys that used to be elsewhere to their place, with a warning
+    fn migrate(&mut self) {
+        if let Some(row_height) = self.window.row_height.take() {
+            eprintln!("fuzzle: window.row_height is deprecated, set theme.row_height instead");
+            self.theme.row_height = row_height;
+        }
+        if let Some(padding) = self.window.padding.take() {
+            eprintln!("fuzzle: window.padding is deprecated, set theme.padding instead");
+            self.theme.padding = padding;
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.theme.validate()?;
+        self.window.validate()?;
+        if self.behavior.max_results == 0 {
+            return Err("behavior.max_results must be at least 1".to_string());
+        }
+        if !self.web.fallback.is_empty() && !self.web.engines.contains_key(&self.web.fallback) {
+            return Err(format!(
+                "web.fallback is \"{}\", but there's no engine with that keyword",
+                self.web.fallback
+            ));
+        }
+        if self.clipboard.poll_interval_ms == 0 {
+            return Err("clipboard.poll_interval_ms must be at least 1".to_string());
+        }
+        for entry in &self.entries {
+            if entry.command.is_some() == entry.url.is_some() {
+                return Err(format!(
+                    "entry \"{}\" must have either a command or a url",
+                    entry.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Path of the selected theme file, if any
+    pub fn theme_path(&self) -> Option<PathBuf> {
+        let name = self.theme_name.as_ref()?;
+        BaseDirectories::with_prefix("fuzzle")
+            .ok()
+            .and_then(|dirs| dirs.find_config_file(format!("themes/{}.toml", name)))
+    }
+
+    /// Put theme and window metrics in the druid `Env`
+    pub fn configure_env(&self, env: &mut Env) {
+        env.set(druid::theme::BORDERED_WIDGET_HEIGHT, 100.);
+        self.theme.apply(env);
+    }
+}
+
+impl WindowConfig {
+    /// Width of the whole window, with the preview pane
+    pub fn total_width(&self) -> f64 {
+        if self.preview {
+            self.width + self.preview_width
+        } else {
+            self.width
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (name, value) in &[("window.width", self.width), ("window.height", self.height)] {
+            if *value <= 0. {
+                return Err(format!("{} must be greater than 0, got {}", name, value));
+            }
+        }
+        if self.preview && self.preview_width <= 0. {
+            return Err(format!(
+                "window.preview_width must be greater than 0, got {}",
+                self.preview_width
+            ));
+        }
+        if self.visible_rows == 0 {
+            return Err("window.visible_rows must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Path of the config file, if it exists in one of the xdg config dirs
+fn config_path() -> Option<PathBuf> {
+    BaseDirectories::with_prefix("fuzzle")
+        .ok()
+        .and_then(|dirs| dirs.find_config_file(CONFIG_FILE))
+}
+
+/// Expand a leading `~` or `~/` to the user's home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match std::env::var_os("HOME") {
+        Some(home) if path == "~" => PathBuf::from(home),
+        Some(home) if path.starts_with("~/") => PathBuf::from(home).join(&path[2..]),
+        _ => PathBuf::from(path),
+    }
+}
@@ -0,0 +1,162 @@
+//! Colors and metrics used by the widgets, and the `Env` keys they are read from.
+use druid::{theme, Color, Env, Key};
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::Path;
+
+use super::expand_home;
+
+pub const MAIN_COLOR: Key<Color> = Key::new("fuzzle.main-color");
+pub const SECONDARY_COLOR: Key<Color> = Key::new("fuzzle.secondary-color");
+pub const SELECTION_COLOR: Key<Color> = Key::new("fuzzle.selection-color");
+pub const HOVER_COLOR: Key<Color> = Key::new("fuzzle.hover-color");
+pub const CURSOR_COLOR: Key<Color> = Key::new("fuzzle.cursor-color");
+pub const HIGHLIGHT_COLOR: Key<Color> = Key::new("fuzzle.highlight-color");
+pub const INPUT_FONT_SIZE: Key<f64> = Key::new("fuzzle.input-font-size");
+pub const NAME_FONT_SIZE: Key<f64> = Key::new("fuzzle.name-font-size");
+pub const DESCRIPTION_FONT_SIZE: Key<f64> = Key::new("fuzzle.description-font-size");
+pub const ROW_HEIGHT: Key<f64> = Key::new("fuzzle.row-height");
+pub const PADDING: Key<f64> = Key::new("fuzzle.padding");
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    #[serde(deserialize_with = "deserialize_color")]
+    pub background: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub text: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub main: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub secondary: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub selection: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub hover: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub cursor: Color,
+    /// Color of the matched characters in results
+    #[serde(deserialize_with = "deserialize_color")]
+    pub highlight: Color,
+    pub background_image: Option<String>,
+    pub font_family: String,
+    pub input_font_size: f64,
+    pub name_font_size: f64,
+    pub description_font_size: f64,
+    pub border_radius: f64,
+    pub row_height: f64,
+    pub padding: f64,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: Color::rgb8(0x39, 0x3d, 0x40),
+            text: Color::rgb8(0xf2, 0xf2, 0xf2),
+            main: Color::rgb8(0xc2, 0xc2, 0xc2),
+            secondary: Color::rgb8(0x92, 0x92, 0x92),
+            selection: Color::rgba8(0xff, 0xff, 0xff, 0x22),
+            hover: Color::rgba8(0xff, 0xff, 0xff, 0x11),
+            cursor: Color::rgb8(0xf2, 0xf2, 0xf2),
+            highlight: Color::rgb8(0xf0, 0xc6, 0x74),
+            background_image: None,
+            font_family: "sans-serif".to_string(),
+            input_font_size: 35.,
+            name_font_size: 20.,
+            description_font_size: 17.,
+            border_radius: 2.,
+            row_height: 75.,
+            padding: 20.,
+        }
+    }
+}
+
+impl Theme {
+    /// Load a standalone theme file, like `themes/nord.toml`.
+    /// Theme files contain the same keys as the `[theme]` section of the config.
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let theme: Theme = toml::from_str(&content).map_err(|err| err.to_string())?;
+        theme.validate()?;
+        Ok(theme)
+    }
+
+    /// Check values that parse fine but make no sense
+    pub fn validate(&self) -> Result<(), String> {
+        if self.font_family.trim().is_empty() {
+            return Err("theme.font_family can't be empty".to_string());
+        }
+        for (name, value) in &[
+            ("theme.input_font_size", self.input_font_size),
+            ("theme.name_font_size", self.name_font_size),
+            ("theme.description_font_size", self.description_font_size),
+            ("theme.row_height", self.row_height),
+        ] {
+            if *value <= 0. {
+                return Err(format!("{} must be greater than 0, got {}", name, value));
+            }
+        }
+        for (name, value) in &[
+            ("theme.border_radius", self.border_radius),
+            ("theme.padding", self.padding),
+        ] {
+            if *value < 0. {
+                return Err(format!("{} can't be negative, got {}", name, value));
+            }
+        }
+        if let Some(image) = &self.background_image {
+            if !expand_home(image).is_file() {
+                return Err(format!(
+                    "theme.background_image \"{}\" is not a file",
+                    image
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write colors and font settings into the druid `Env`
+    pub fn apply(&self, env: &mut Env) {
+        env.set(theme::WINDOW_BACKGROUND_COLOR, self.background.clone());
+        env.set(theme::BACKGROUND_LIGHT, self.background.clone());
+        env.set(theme::LABEL_COLOR, self.text.clone());
+        env.set(theme::FONT_NAME, self.font_family.as_str());
+        env.set(theme::TEXT_SIZE_NORMAL, self.name_font_size);
+        env.set(theme::TEXTBOX_BORDER_RADIUS, self.border_radius);
+        env.set(MAIN_COLOR, self.main.clone());
+        env.set(SECONDARY_COLOR, self.secondary.clone());
+        env.set(SELECTION_COLOR, self.selection.clone());
+        env.set(HOVER_COLOR, self.hover.clone());
+        env.set(CURSOR_COLOR, self.cursor.clone());
+        env.set(HIGHLIGHT_COLOR, self.highlight.clone());
+        env.set(ROW_HEIGHT, self.row_height);
+        env.set(PADDING, self.padding);
+        env.set(INPUT_FONT_SIZE, self.input_font_size);
+        env.set(NAME_FONT_SIZE, self.name_font_size);
+        env.set(DESCRIPTION_FONT_SIZE, self.description_font_size);
+    }
+}
+
+/// Parse a color in the `#rrggbb` or `#rrggbbaa` form
+pub fn parse_color(value: &str) -> Result<Color, String> {
+    let hex = value.trim().trim_start_matches('#');
+    let valid = (hex.len() == 6 || hex.len() == 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    if !valid {
+        return Err(format!(
+            "invalid color \"{}\", expected #rrggbb or #rrggbbaa",
+            value
+        ));
+    }
+    let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    let alpha = if hex.len() == 8 { component(6) } else { 0xff };
+    let (red, green, blue) = (component(0), component(2), component(4));
+    Ok(Color::rgba8(red, green, blue, alpha))
+}
+
+fn deserialize_color<'de, D>(deserializer: D) -> Result<Color, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    parse_color(&value).map_err(serde::de::Error::custom)
+}
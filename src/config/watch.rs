@@ -0,0 +1,59 @@
+//! Reload the theme file when it changes on disk.
+use druid::{ExtEventSink, Selector};
+use inotify::{Inotify, WatchMask};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+use super::Theme;
+
+/// Sent with an `Arc<Theme>` every time the theme file is saved
+pub const THEME_CHANGED: Selector = Selector::new("fuzzle.theme-changed");
+
+/// Watch the theme file in a background thread and submit `THEME_CHANGED`
+/// with the new theme every time it's written.
+///
+/// We watch the parent directory rather than the file itself, because most
+/// editors save by writing a new file and renaming it over the old one.
+pub fn watch_theme(path: PathBuf, sink: ExtEventSink) {
+    let (dir, file_name) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(file_name)) => (dir.to_owned(), file_name.to_owned()),
+        _ => return,
+    };
+    thread::spawn(move || {
+        let mut inotify = match Inotify::init() {
+            Ok(inotify) => inotify,
+            Err(err) => return eprintln!("fuzzle: can't watch theme file: {}", err),
+        };
+        let mask = WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE;
+        if let Err(err) = inotify.add_watch(&dir, mask) {
+            return eprintln!("fuzzle: can't watch {}: {}", dir.display(), err);
+        }
+        let mut buffer = [0; 4096];
+        loop {
+            let events = match inotify.read_events_blocking(&mut buffer) {
+                Ok(events) => events,
+                Err(_) => return,
+            };
+            if !events
+                .into_iter()
+                .any(|e| e.name == Some(file_name.as_os_str()))
+            {
+                continue;
+            }
+            match Theme::load_file(&path) {
+                Ok(theme) => {
+                    // The window is gone, nothing left to do
+                    if sink
+                        .submit_command(THEME_CHANGED, Arc::new(theme), None)
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                // Keep the current theme while the file is broken
+                Err(msg) => eprintln!("fuzzle: error in {}: {}", path.display(), msg),
+            }
+        }
+    });
+}
@@ -1,93 +1,638 @@
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
 use std::sync::Arc;
 
-use fuzzy_matcher::skim::SkimMatcherV2;
-use fuzzy_matcher::FuzzyMatcher;
-use serde::{Deserialize, Serialize};
-
 use druid::{
-    AppDelegate, Command, DelegateCtx, Env, Event, HotKey, KeyCode, SysMods, Target, WindowId,
+    AppDelegate, Application, Command, DelegateCtx, Env, Event, ExtEventSink, Target, WindowId,
 };
 
+use crate::config::{Action, Config, CustomEntry, Theme, THEME_CHANGED};
+use crate::hidden;
+use crate::launch::{self, exec_argvs, ResultAction};
+use crate::preview::{self, PreviewLoader, PREVIEW_READY};
+use crate::providers::{clipboard, Answers, Providers};
+use crate::search::{Match, MatchedField, SearchResponse, SearchWorker, SEARCH_FINISHED};
+use crate::state::{Preview, Provider};
+use crate::widgets::{ACTIONS_REQUESTED, QUERY_CHANGED, RESULTS_SCROLLED, RESULT_CLICKED};
 use crate::{AppState, SearchResult};
 
-use crate::dirutils::build_cache;
+use crate::dirutils::{build_cache, searchresult_from_custom};
+
+const CACHE_PATH: &str = "/tmp/fuzzle_cache.bincode";
+/// Written before the cache. Change it whenever `SearchResult` or
+/// `Provider` change, older caches are then rebuilt instead of misread.
+const CACHE_FORMAT: &str = "fuzzle cache 1";
+
+/// What the list shows, and what Enter does
+enum Mode {
+    Search,
+    /// The actions menu of a result. `back` is the selected line and the
+    /// scroll offset to go back to.
+    Actions {
+        result: SearchResult,
+        actions: Vec<ResultAction>,
+        back: (usize, usize),
+    },
+    /// The input holds arguments for a result, `query` is the search to go back to
+    Arguments {
+        result: SearchResult,
+        query: String,
+        back: (usize, usize),
+    },
+    /// A result that ends the session or turns the computer off, shown
+    /// alone until Enter launches it
+    Confirm {
+        result: SearchResult,
+        back: (usize, usize),
+    },
+}
 
-#[derive(Serialize, Deserialize)]
 pub struct Delegate {
-    #[serde(skip)]
-    matcher: SkimMatcherV2,
-    cache: Vec<SearchResult>,
+    config: Arc<Config>,
+    worker: SearchWorker,
+    previews: PreviewLoader,
+    /// Matches of the last search, sorted
+    results: Arc<Vec<Match>>,
+    /// Generation of the query `results` are for, they are stale while
+    /// the worker is on a newer one
+    results_generation: usize,
+    /// Enter was pressed before the results of the query came, with
+    /// this `close`. It's acted on when they come.
+    pending_activation: Option<bool>,
+    /// The entries those matches point into
+    entries: Arc<Vec<SearchResult>>,
+    cache: Arc<Vec<SearchResult>>,
+    providers: Providers,
+    /// What the providers made of the query
+    answers: Answers,
+    /// Arguments typed after the search in the query
+    arguments: Vec<String>,
+    mode: Mode,
 }
 
 impl Delegate {
-    pub fn new() -> Self {
+    pub fn new(config: Arc<Config>, sink: ExtEventSink) -> Self {
         Self {
-            matcher: SkimMatcherV2::default(),
-            cache: match fs::File::open("/tmp/fuzzle_cache.bincode") {
-                Ok(file) => match bincode::deserialize_from::<fs::File, Delegate>(file) {
-                    Ok(delegate) => delegate.cache,
-                    Err(_) => vec![],
-                },
-                Err(_) => vec![],
-            },
+            worker: SearchWorker::spawn(sink.clone(), &config.behavior),
+            previews: PreviewLoader::spawn(sink),
+            providers: Providers::new(&config),
+            answers: Answers::default(),
+            arguments: vec![],
+            config,
+            results: Arc::new(vec![]),
+            results_generation: 0,
+            pending_activation: None,
+            entries: Arc::new(vec![]),
+            cache: Arc::new(load_cache(&config.entries)),
+            mode: Mode::Search,
         }
     }
 
     fn populate_cache(&mut self) {
-        self.cache = build_cache();
-        // Reset search results
-        if let Ok(file) = fs::File::create("/tmp/fuzzle_cache.bincode") {
-            bincode::serialize_into(file, self).unwrap();
-        }
+        self.cache = Arc::new(build_cache(&self.config.providers, &self.config.entries));
+        save_cache(&self.cache);
     }
 
-    fn search(&mut self, data: &AppState) -> (usize, Vec<SearchResult>) {
-        // Search in all the cache so we have score for each entry
-        let mut res: Vec<SearchResult> = self
+    /// Search the cache without the hidden results, and let the providers see all of it
+    fn set_cache(&mut self) {
+        let hidden = hidden::load();
+        let cache = self
             .cache
             .iter()
-            .filter_map(|sr| {
-                let mut search_name = String::from(&sr.name);
-                if let Some(file_name) =
-                    Path::new(sr.desktop_entry_path.as_ref().unwrap_or(&"".to_string())).file_stem()
-                {
-                    search_name = search_name + " " + file_name.to_str().unwrap_or("");
-                };
-                let result = self.matcher.fuzzy_indices(&search_name, &data.input_text);
-
-                if let Some((score, indices)) = result {
-                    Some(SearchResult {
-                        // Always put desktop entry files first
-                        score: if sr.desktop_entry_path.is_some() {
-                            score + 1000
-                        } else {
-                            score
-                        },
-                        indices: Arc::new(indices),
-                        selected: false,
-                        ..sr.clone()
-                    })
-                } else {
-                    None
+            .filter(|result| !hidden.contains(&result.id()))
+            .cloned()
+            .collect();
+        self.worker.set_cache(Arc::new(cache));
+        self.providers.set_cache(&self.cache);
+    }
+
+    /// Ask the providers about a new query, and search for it unless
+    /// it's meant for one of them
+    fn query(&mut self, data: &mut AppState, query: &str) {
+        self.pending_activation = None;
+        self.answers = self.providers.answer(query);
+        if !self.answers.exclusive {
+            let (search, arguments) = launch::split_query(query);
+            self.arguments = arguments;
+            self.worker.search(search);
+            return;
+        }
+        // Nothing else to wait for
+        self.arguments.clear();
+        self.results = Arc::new(vec![]);
+        self.results_generation = self.worker.generation();
+        data.selected_line = 0;
+        data.scroll_offset = 0;
+        data.search_results = Arc::new(self.visible_results(data));
+    }
+
+    /// The provider results that come before the matches. Fallbacks are
+    /// shown when nothing matches, whatever comes after the matches.
+    fn answer_rows(&self) -> &[SearchResult] {
+        let answers = &self.answers;
+        if answers.first.is_empty() && self.results.is_empty() {
+            &answers.fallback
+        } else {
+            &answers.first
+        }
+    }
+
+    /// The matches of a search, without the results the providers
+    /// already show above them, like the result of an alias
+    fn without_answers(&self, response: &SearchResponse) -> Arc<Vec<Match>> {
+        let answered: HashSet<String> = self.answers.first.iter().map(SearchResult::id).collect();
+        if answered.is_empty() {
+            return response.matches.clone();
+        }
+        let matches = response
+            .matches
+            .iter()
+            .filter(|m| !answered.contains(&response.entries[m.entry].id()))
+            .cloned()
+            .collect();
+        Arc::new(matches)
+    }
+
+    /// Where the matches are in the list
+    fn match_rows(&self) -> std::ops::Range<usize> {
+        let start = self.answer_rows().len();
+        start..start + self.results.len()
+    }
+
+    /// The arguments of the query for the result at some line, only the
+    /// matches take them
+    fn arguments(&self, line: usize) -> &[String] {
+        if self.match_rows().contains(&line) {
+            &self.arguments
+        } else {
+            &[]
+        }
+    }
+
+    /// How many rows the list has in the current mode
+    fn row_count(&self) -> usize {
+        match &self.mode {
+            Mode::Search => self.match_rows().end + self.answers.last.len(),
+            Mode::Actions { actions, .. } => actions.len(),
+            Mode::Arguments { .. } | Mode::Confirm { .. } => 1,
+        }
+    }
+
+    /// The row at some position of the list, without its place in it
+    fn row(&self, position: usize) -> SearchResult {
+        match &self.mode {
+            Mode::Search => {
+                let matches = self.match_rows();
+                if position < matches.start {
+                    return self.answer_rows()[position].clone();
                 }
+                if position >= matches.end {
+                    return self.answers.last[position - matches.end].clone();
+                }
+                // Indices come with the match, they are in the name or in
+                // the fields after it. The description isn't searched.
+                let m = &self.results[position - matches.start];
+                SearchResult {
+                    score: m.score,
+                    indices: m.indices.clone(),
+                    ..self.entries[m.entry].clone()
+                }
+            }
+            Mode::Actions {
+                result, actions, ..
+            } => action_row(result, &actions[position], &self.config.launch.terminal),
+            Mode::Arguments { result, .. } | Mode::Confirm { result, .. } => result.clone(),
+        }
+    }
+
+    /// Keep the selected line inside the rows and scroll the viewport
+    /// so that it's visible, then return the rows to show and describe
+    /// the selected one.
+    fn visible_results(&self, data: &mut AppState) -> Vec<SearchResult> {
+        let visible_rows = self.config.window.visible_rows;
+        let len = self.row_count();
+        data.num_results = len;
+        data.selected_line = data.selected_line.min(len.max(1) - 1);
+
+        if data.selected_line < data.scroll_offset {
+            data.scroll_offset = data.selected_line;
+        } else if data.selected_line >= data.scroll_offset + visible_rows {
+            data.scroll_offset = data.selected_line + 1 - visible_rows;
+        }
+        data.scroll_offset = data.scroll_offset.min(len.saturating_sub(visible_rows));
+
+        // Only the visible rows are turned into full results
+        let last = (data.scroll_offset + visible_rows).min(len);
+        let visible: Vec<SearchResult> = (data.scroll_offset..last)
+            .map(|position| SearchResult {
+                position,
+                selected: position == data.selected_line,
+                ..self.row(position)
             })
             .collect();
+        data.detail = self.detail(&visible);
+        if self.config.window.preview {
+            self.update_preview(data, &visible);
+        }
+        visible
+    }
+
+    /// Describe the selected result, the indicator elides it to fit.
+    /// In the actions menu and while typing arguments, say what they are for,
+    /// and ask before a result that must be confirmed.
+    fn detail(&self, visible: &[SearchResult]) -> String {
+        match &self.mode {
+            Mode::Actions { result, .. } => return format!("Actions of {}", result.name),
+            Mode::Arguments { result, .. } => {
+                return format!("Arguments for {}, Escape to go back", result.name)
+            }
+            Mode::Confirm { result, .. } => {
+                return format!("{}? Enter to confirm, Escape to go back", result.name)
+            }
+            Mode::Search => (),
+        }
+        if !self.config.window.show_details {
+            return String::new();
+        }
+        visible
+            .iter()
+            .find(|result| result.selected)
+            .map(|result| describe(result, self.arguments(result.position)))
+            .unwrap_or_default()
+    }
+
+    /// Ask for a preview of the selected result, unless it's already
+    /// there. Its name shows until the loader sends the rest.
+    fn update_preview(&self, data: &mut AppState, visible: &[SearchResult]) {
+        match visible.iter().find(|result| result.selected) {
+            Some(result) if data.preview.key != result.id() => {
+                data.preview = Arc::new(preview::placeholder(result));
+                self.previews.load(result);
+            }
+            Some(_) => (),
+            None => data.preview = Arc::new(Preview::default()),
+        }
+    }
+
+    /// The result at some line of the search results
+    fn result(&self, line: usize) -> Option<&SearchResult> {
+        let matches = self.match_rows();
+        if line < matches.start {
+            self.answer_rows().get(line)
+        } else if line < matches.end {
+            let m = &self.results[line - matches.start];
+            Some(&self.entries[m.entry])
+        } else {
+            self.answers.last.get(line - matches.end)
+        }
+    }
+
+    /// The result the list is about: the selected one while searching,
+    /// the one whose menu, arguments or confirmation is shown otherwise
+    fn current_result(&self, data: &AppState) -> Option<&SearchResult> {
+        match &self.mode {
+            Mode::Search => self.result(data.selected_line),
+            Mode::Actions { result, .. }
+            | Mode::Arguments { result, .. }
+            | Mode::Confirm { result, .. } => Some(result),
+        }
+    }
+
+    /// Launch the selected result, run the selected action of the menu,
+    /// launch with the arguments typed, or launch the result being
+    /// confirmed. If `close` is true, exit once something started.
+    fn activate(&mut self, data: &mut AppState, close: bool) {
+        match std::mem::replace(&mut self.mode, Mode::Search) {
+            Mode::Search => {
+                match self.result(data.selected_line).cloned() {
+                    // Copied, not launched
+                    Some(result) if result.provider.is_copied() => {
+                        let copied = copy(&result.command);
+                        self.launched(&result, copied, close);
+                    }
+                    Some(result) if self.providers.confirms(&result) => self.confirm(data, result),
+                    Some(result) => {
+                        let args = self.arguments(data.selected_line).to_vec();
+                        let terminal = &self.config.launch.terminal;
+                        let started = launch::launch(terminal, &result, &args);
+                        self.launched(&result, started, close);
+                    }
+                    None => (),
+                }
+            }
+            Mode::Actions {
+                result,
+                actions,
+                back,
+            } => {
+                let action = actions.get(data.selected_line).cloned();
+                data.selected_line = back.0;
+                data.scroll_offset = back.1;
+                if let Some(action) = action {
+                    self.run(data, result, action, back, close);
+                }
+            }
+            Mode::Arguments {
+                result,
+                query,
+                back,
+            } => {
+                let args = launch::split_arguments(&data.input_text);
+                data.input_text = query;
+                data.selected_line = back.0;
+                data.scroll_offset = back.1;
+                let started = launch::launch(&self.config.launch.terminal, &result, &args);
+                self.launched(&result, started, close);
+            }
+            Mode::Confirm { result, back } => {
+                data.selected_line = back.0;
+                data.scroll_offset = back.1;
+                let started = launch::launch(&self.config.launch.terminal, &result, &[]);
+                self.launched(&result, started, close);
+            }
+        }
+    }
+
+    /// Show a result alone, and ask before launching it
+    fn confirm(&mut self, data: &mut AppState, result: SearchResult) {
+        self.mode = Mode::Confirm {
+            result,
+            back: (data.selected_line, data.scroll_offset),
+        };
+        data.selected_line = 0;
+        data.scroll_offset = 0;
+    }
+
+    /// Activate, unless the search results still belong to an older query.
+    /// Then wait for them, so Enter right after typing launches what was typed.
+    fn activate_when_ready(&mut self, data: &mut AppState, close: bool) {
+        let stale = self.results_generation != self.worker.generation();
+        match self.mode {
+            Mode::Search if stale => self.pending_activation = Some(close),
+            _ => self.activate(data, close),
+        }
+    }
+
+    /// Do something picked from the actions menu of a result
+    fn run(
+        &mut self,
+        data: &mut AppState,
+        result: SearchResult,
+        action: ResultAction,
+        back: (usize, usize),
+        close: bool,
+    ) {
+        let terminal = self.config.launch.terminal.clone();
+        let args = self.arguments(back.0).to_vec();
+        let started = match action {
+            ResultAction::Launch if self.providers.confirms(&result) => {
+                self.confirm(data, result);
+                return;
+            }
+            ResultAction::Launch => launch::launch(&terminal, &result, &args),
+            ResultAction::LaunchInTerminal => {
+                launch::in_terminal(&terminal, &launch::command_lines(&result, &args))
+            }
+            ResultAction::LaunchWithArguments => {
+                let query = std::mem::take(&mut data.input_text);
+                self.mode = Mode::Arguments {
+                    result,
+                    query,
+                    back,
+                };
+                return;
+            }
+            ResultAction::OpenFolder => launch::open_folder(&result),
+            ResultAction::CopyCommand => copy(&result.command),
+            ResultAction::CopyDesktopPath => match &result.desktop_entry_path {
+                Some(path) => copy(path),
+                None => false,
+            },
+            ResultAction::EditDesktopEntry => match &result.desktop_entry_path {
+                Some(path) => launch::edit(&terminal, path),
+                None => false,
+            },
+            ResultAction::Hide => {
+                if hidden::hide(&result.id()).is_ok() {
+                    self.set_cache();
+                    let query = data.input_text.clone();
+                    self.query(data, &query);
+                }
+                false
+            }
+            ResultAction::Forget | ResultAction::ClearHistory => {
+                if let ResultAction::Forget = action {
+                    self.providers.forget(&result);
+                } else {
+                    self.providers.clear_history(&result);
+                }
+                let query = data.input_text.clone();
+                self.query(data, &query);
+                false
+            }
+            ResultAction::Run { command, .. } => {
+                launch::spawn_each(&exec_argvs(&command, &result, &args))
+            }
+        };
+        self.launched(&result, started, close);
+    }
+
+    /// Tell the providers something started, then exit if asked to
+    fn launched(&mut self, result: &SearchResult, started: bool, close: bool) {
+        if !started {
+            return;
+        }
+        self.providers.launched(result);
+        if close {
+            std::process::exit(0)
+        }
+    }
+
+    /// Complete the path at the end of the query, or replace the query with
+    /// the selected result when it completes it
+    fn complete(&mut self, data: &mut AppState) {
+        if self.complete_path(data) {
+            return;
+        }
+        let completion = match self.mode {
+            Mode::Search => self
+                .result(data.selected_line)
+                .and_then(|result| self.providers.complete(&data.input_text, result)),
+            _ => None,
+        };
+        if let Some(completion) = completion {
+            data.input_text = completion;
+            let query = data.input_text.clone();
+            self.query(data, &query);
+        }
+    }
 
-        // Now order by score, descending
-        res.sort_unstable_by_key(|a| -a.score);
+    /// Complete the path at the end of the input, return false if there's none
+    fn complete_path(&mut self, data: &mut AppState) -> bool {
+        let completion = match self.mode {
+            Mode::Search | Mode::Arguments { .. } => {
+                launch::complete_last_argument(&data.input_text)
+            }
+            Mode::Actions { .. } | Mode::Confirm { .. } => None,
+        };
+        let completion = match completion {
+            Some(completion) => completion,
+            None => return false,
+        };
+        data.input_text = completion;
+        if let Mode::Search = self.mode {
+            let query = data.input_text.clone();
+            self.query(data, &query);
+        }
+        true
+    }
 
-        // Select the line
-        let len = res.len();
-        if len > data.selected_line {
-            res[data.selected_line].selected = true;
+    /// Open the actions menu of the selected result, or close it if it's open
+    fn toggle_actions(&mut self, data: &mut AppState) {
+        if self.close_menu(data) {
+            return;
+        }
+        if let Some(result) = self.result(data.selected_line).cloned() {
+            let mut actions = launch::actions(&result);
+            actions.append(&mut self.providers.actions(&result));
+            self.mode = Mode::Actions {
+                actions,
+                result,
+                back: (data.selected_line, data.scroll_offset),
+            };
+            data.selected_line = 0;
+            data.scroll_offset = 0;
         }
+    }
+
+    /// Go back to the search from the actions menu, the arguments or a
+    /// confirmation, return false if there was nothing to go back from
+    fn close_menu(&mut self, data: &mut AppState) -> bool {
+        let back = match std::mem::replace(&mut self.mode, Mode::Search) {
+            Mode::Search => return false,
+            Mode::Actions { back, .. } | Mode::Confirm { back, .. } => back,
+            Mode::Arguments { query, back, .. } => {
+                data.input_text = query;
+                back
+            }
+        };
+        data.selected_line = back.0;
+        data.scroll_offset = back.1;
+        true
+    }
+
+    /// Scroll the viewport by some lines, dragging the selection along
+    fn scroll(&self, data: &mut AppState, lines: isize) {
+        let visible_rows = self.config.window.visible_rows;
+        let max_offset = self.row_count().saturating_sub(visible_rows) as isize;
+        let offset = (data.scroll_offset as isize + lines).max(0).min(max_offset) as usize;
+        data.scroll_offset = offset;
+        data.selected_line = data
+            .selected_line
+            .max(offset)
+            .min(offset + visible_rows - 1);
+    }
+}
+
+/// The cache of the last run. It's empty, and rebuilt, if it's in another
+/// format or was built with other entries in the config.
+fn load_cache(entries: &[CustomEntry]) -> Vec<SearchResult> {
+    let mut file = match fs::File::open(CACHE_PATH) {
+        Ok(file) => file,
+        Err(_) => return vec![],
+    };
+    match bincode::deserialize_from::<_, String>(&mut file) {
+        Ok(format) if format == CACHE_FORMAT => (),
+        _ => return vec![],
+    }
+    match bincode::deserialize_from::<_, Vec<SearchResult>>(file) {
+        Ok(cache) if entries_unchanged(&cache, entries) => cache,
+        _ => vec![],
+    }
+}
 
-        (
-            len,
-            res[data.selected_line.max(1) - 1..(data.selected_line + 3).min(len)].to_vec(),
-        )
+fn save_cache(cache: &[SearchResult]) {
+    let saved = fs::File::create(CACHE_PATH)
+        .map_err(bincode::Error::from)
+        .and_then(|mut file| {
+            bincode::serialize_into(&mut file, CACHE_FORMAT)?;
+            bincode::serialize_into(file, cache)
+        });
+    if let Err(e) = saved {
+        eprintln!("fuzzle: can't save the cache: {}", e);
+    }
+}
+
+/// Whether the entries of the config in a cache are the ones of the
+/// config now, icons included
+fn entries_unchanged(cache: &[SearchResult], entries: &[CustomEntry]) -> bool {
+    let cached = cache
+        .iter()
+        .filter(|result| result.provider == Provider::Custom);
+    let configured: Vec<SearchResult> = entries.iter().map(searchresult_from_custom).collect();
+    cached.eq(configured.iter())
+}
+
+/// Copy text so that it stays in the clipboard after fuzzle exits. Without
+/// `wl-copy` or `xclip` it's only there while fuzzle runs, then return
+/// false so that fuzzle stays open.
+fn copy(text: &str) -> bool {
+    if clipboard::copy(text) {
+        return true;
+    }
+    Application::clipboard().put_string(text);
+    false
+}
+
+/// What a result runs with the arguments of the query, its desktop file,
+/// why it matched and where it comes from
+fn describe(result: &SearchResult, args: &[String]) -> String {
+    let quote = |arg: String| {
+        if arg.contains(' ') {
+            format!("'{}'", arg)
+        } else {
+            arg
+        }
+    };
+    let mut parts: Vec<String> = launch::command_lines(result, args)
+        .into_iter()
+        .filter(|argv| !argv.is_empty())
+        .map(|argv| argv.into_iter().map(quote).collect::<Vec<_>>().join(" "))
+        .collect();
+    if let Some(path) = &result.desktop_entry_path {
+        parts.push(path.clone());
+    }
+    if let Some(field) = MatchedField::of(result, &result.indices) {
+        parts.push(format!("{} matched", field.name()));
+    }
+    parts.push(result.provider.name().to_string());
+    parts.join(" · ")
+}
+
+/// A row of the actions menu: the action, and what it acts on
+fn action_row(result: &SearchResult, action: &ResultAction, terminal: &str) -> SearchResult {
+    let file = result
+        .desktop_entry_path
+        .as_ref()
+        .unwrap_or(&result.command);
+    let description = match action {
+        ResultAction::LaunchInTerminal => format!("{} {}", terminal, result.command),
+        ResultAction::OpenFolder
+        | ResultAction::CopyDesktopPath
+        | ResultAction::EditDesktopEntry => file.clone(),
+        ResultAction::Hide | ResultAction::Forget => result.name.clone(),
+        ResultAction::ClearHistory => "Everything recorded by fuzzle --clip-daemon".to_string(),
+        ResultAction::Run { command, .. } => command.clone(),
+        // The command is the text itself, which can be long
+        _ if result.provider.is_copied() => result.name.clone(),
+        _ => result.command.clone(),
+    };
+    SearchResult {
+        name: action.label(),
+        description,
+        indices: Arc::new(vec![]),
+        description_indices: Arc::new(vec![]),
+        ..result.clone()
     }
 }
 
@@ -100,44 +645,60 @@ impl AppDelegate<AppState> for Delegate {
         data: &mut AppState,
         _env: &Env,
     ) -> Option<Event> {
-        let (num_results, results) = self.search(&data);
         if let Event::KeyDown(key_event) = event {
-            match key_event {
-                ke if ke.key_code == KeyCode::Escape => std::process::exit(0),
-                ke if ke.key_code == KeyCode::Return => {
-                    // TODO: use data.selected_line. The unwrap should never panic here,
-                    // but it's not nice anyway
-                    let command = results.iter().find(|r| r.selected).unwrap().command.clone();
-                    let command = command.split_whitespace().next().unwrap();
-                    if std::process::Command::new(command).spawn().is_ok() {
+            let num_results = self.row_count();
+            let visible_rows = self.config.window.visible_rows;
+            // Without a path to complete, the key does what else it's bound to
+            let mut action = self
+                .config
+                .keys
+                .actions(&key_event)
+                .into_iter()
+                .find(|action| *action != Action::CompletePath || self.complete_path(data));
+            // A held Enter that launched the result must not confirm it too,
+            // other keys repeat as usual
+            if let (Mode::Confirm { .. }, true, Some(Action::Activate | Action::ActivateAlt)) =
+                (&self.mode, key_event.is_repeat, action)
+            {
+                action = None;
+            }
+            match action {
+                Some(Action::Quit) => {
+                    if !self.close_menu(data) {
                         std::process::exit(0)
                     }
                 }
-                ke if (HotKey::new(SysMods::Cmd, "j")).matches(ke)
-                    || (HotKey::new(SysMods::Cmd, "n")).matches(ke)
-                    || (!ke.mods.shift && (ke.key_code == KeyCode::Tab))
-                    || ke.key_code == KeyCode::ArrowDown =>
-                {
-                    data.selected_line = data.selected_line.min(num_results - 2) + 1;
+                Some(Action::Activate) => self.activate_when_ready(data, true),
+                // Launch, but keep fuzzle open to launch something else
+                Some(Action::ActivateAlt) => self.activate_when_ready(data, false),
+                Some(Action::Actions) => self.toggle_actions(data),
+                Some(Action::Complete) => self.complete(data),
+                Some(Action::CopyCommand) => {
+                    if let Some(result) = self.current_result(data) {
+                        copy(&result.command);
+                    }
                 }
-
-                ke if (HotKey::new(SysMods::Cmd, "k")).matches(ke)
-                    || (HotKey::new(SysMods::Cmd, "p")).matches(ke)
-                    || (ke.mods.shift && (ke.key_code == KeyCode::Tab))
-                    || ke.key_code == KeyCode::ArrowUp =>
-                {
-                    data.selected_line = data.selected_line.max(1) - 1;
+                Some(Action::CycleMatchMode) => {
+                    data.match_mode = data.match_mode.next();
+                    let extended = self.config.behavior.extended_syntax;
+                    self.worker.set_mode(data.match_mode, extended);
+                    let query = data.input_text.clone();
+                    self.query(data, &query);
                 }
-                k_e if k_e.key_code.is_printable()
-                    || (HotKey::new(None, KeyCode::Backspace)).matches(k_e) =>
-                {
-                    // Reset selected line if new text comes
-                    data.selected_line = 0;
+                Some(Action::Next) => data.selected_line += 1,
+                Some(Action::Prev) => data.selected_line = data.selected_line.saturating_sub(1),
+                Some(Action::PageDown) => data.selected_line += visible_rows,
+                Some(Action::PageUp) => {
+                    data.selected_line = data.selected_line.saturating_sub(visible_rows)
                 }
+                Some(Action::First) => data.selected_line = 0,
+                Some(Action::Last) => data.selected_line = num_results.saturating_sub(1),
+                // Editing is done by the textbox, and it tells
+                // us with QUERY_CHANGED when the text changes
                 _ => (),
             }
+            data.search_results = Arc::new(self.visible_results(data));
         };
-        data.search_results = Arc::new(results);
         Some(event)
     }
 
@@ -145,17 +706,104 @@ impl AppDelegate<AppState> for Delegate {
         &mut self,
         _d: &mut DelegateCtx,
         _t: &Target,
-        _c: &Command,
-        _a: &mut AppState,
+        cmd: &Command,
+        data: &mut AppState,
         _e: &Env,
     ) -> bool {
-        false
+        if cmd.selector == THEME_CHANGED {
+            if let Ok(theme) = cmd.get_object::<Arc<Theme>>() {
+                data.theme = theme.clone();
+            }
+            return false;
+        }
+        if cmd.selector == QUERY_CHANGED {
+            if let Ok(query) = cmd.get_object::<String>() {
+                match self.mode {
+                    // The input holds arguments, not a query
+                    Mode::Arguments { .. } => (),
+                    // Typing in the actions menu or instead of confirming
+                    // goes back to the search
+                    Mode::Actions { .. } | Mode::Confirm { .. } => {
+                        self.mode = Mode::Search;
+                        self.query(data, query);
+                    }
+                    Mode::Search => self.query(data, query),
+                }
+            }
+            return false;
+        }
+        if cmd.selector == ACTIONS_REQUESTED {
+            if let Mode::Search = self.mode {
+                self.toggle_actions(data);
+                data.search_results = Arc::new(self.visible_results(data));
+            }
+            return false;
+        }
+        if cmd.selector == SEARCH_FINISHED {
+            if let Ok(response) = cmd.get_object::<SearchResponse>() {
+                // Results for a query that is not the latest one, or
+                // for one that was meant for a provider since
+                if response.generation != self.worker.generation() || self.answers.exclusive {
+                    return false;
+                }
+                self.results = self.without_answers(response);
+                self.results_generation = response.generation;
+                self.entries = response.entries.clone();
+                // The menu stays up, the new results show when it closes
+                match self.mode {
+                    Mode::Search => (),
+                    _ => return false,
+                }
+                data.selected_line = 0;
+                data.scroll_offset = 0;
+                if let Some(close) = self.pending_activation.take() {
+                    self.activate(data, close);
+                }
+                data.search_results = Arc::new(self.visible_results(data));
+            }
+            return false;
+        }
+        if cmd.selector == PREVIEW_READY {
+            if let Ok(preview) = cmd.get_object::<Arc<Preview>>() {
+                // Another result was selected since
+                if preview.key == data.preview.key {
+                    data.preview = preview.clone();
+                }
+            }
+            return false;
+        }
+        if cmd.selector == RESULT_CLICKED {
+            // The clicks of a double click land on the result asking to be
+            // confirmed, only a key confirms it
+            if let Mode::Confirm { .. } = self.mode {
+                return false;
+            }
+            if let Ok((position, clicks)) = cmd.get_object::<(usize, u32)>() {
+                data.selected_line = *position;
+                if *clicks > 1 || self.config.behavior.click_to_launch {
+                    self.activate(data, true);
+                }
+                data.search_results = Arc::new(self.visible_results(data));
+            }
+            return false;
+        }
+        if cmd.selector == RESULTS_SCROLLED {
+            if let Ok(lines) = cmd.get_object::<isize>() {
+                self.scroll(data, *lines);
+                data.search_results = Arc::new(self.visible_results(data));
+            }
+            return false;
+        }
+        true
     }
 
-    fn window_added(&mut self, _i: WindowId, _d: &mut AppState, _e: &Env, _c: &mut DelegateCtx) {
+    fn window_added(&mut self, _i: WindowId, data: &mut AppState, _e: &Env, _c: &mut DelegateCtx) {
         if self.cache.is_empty() {
             self.populate_cache();
         }
+        self.set_cache();
+        let query = data.input_text.clone();
+        self.query(data, &query);
     }
     fn window_removed(&mut self, _i: WindowId, _d: &mut AppState, _e: &Env, _c: &mut DelegateCtx) {}
 }
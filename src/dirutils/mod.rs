@@ -2,10 +2,11 @@ use ini::Ini;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
 use walkdir::WalkDir;
 use xdg::BaseDirectories;
 
+use crate::config::{CustomEntry, ProvidersConfig};
+use crate::state::Provider;
 use crate::SearchResult;
 
 fn search_dirs() -> Vec<PathBuf> {
@@ -18,7 +19,7 @@ fn search_dirs() -> Vec<PathBuf> {
 }
 
 /// Given an icon name, search for the icon file.
-fn search_icon(icon: &str) -> Option<String> {
+pub(crate) fn search_icon(icon: &str) -> Option<String> {
     // Get data dirs, add "icons" (/usr/share/icons ecc...)
     let mut data_dirs: Vec<String> = search_dirs()
         .iter()
@@ -78,6 +79,10 @@ fn searchresult_from_desktopentry(desktop_file_path: &Path) -> Option<SearchResu
         Some(command) => command.to_string(),
         None => return None,
     };
+    let keywords = section
+        .get("Keywords")
+        .map(|keywords| keywords.to_string())
+        .unwrap_or_default();
 
     let desktop_entry_path = match desktop_file_path.to_str() {
         Some(path) => Some(path.to_string()),
@@ -87,12 +92,8 @@ fn searchresult_from_desktopentry(desktop_file_path: &Path) -> Option<SearchResu
     Some(SearchResult {
         icon_path: search_icon(icon),
         desktop_entry_path,
-        name,
-        description,
-        command,
-        score: 0,
-        selected: false,
-        indices: Arc::new(vec![]),
+        keywords,
+        ..SearchResult::new(Provider::DesktopEntry, name, description, command)
     })
 }
 
@@ -117,47 +118,65 @@ fn searchresult_from_bin(command_path: &Path) -> Option<SearchResult> {
 
     Some(SearchResult {
         icon_path: search_icon("terminal"),
-        desktop_entry_path: None,
-        name,
-        description,
-        command,
-        score: 0,
-        selected: false,
-        indices: Arc::new(vec![]),
+        ..SearchResult::new(Provider::Binary, name, description, command)
     })
 }
 
-/// Search all applications and collect them in a Vec of SearchResult
-/// This should be the only public api in this module.
-pub fn build_cache() -> Vec<SearchResult> {
-    let mut results: Vec<SearchResult> = Vec::new();
+/// Build a SearchResult for an entry of the config
+pub fn searchresult_from_custom(entry: &CustomEntry) -> SearchResult {
+    // The icon is either a path or a name from the icon theme
+    let icon_path = match &entry.icon {
+        Some(icon) if Path::new(icon).is_absolute() => Some(icon.clone()),
+        Some(icon) => search_icon(icon),
+        None => None,
+    };
+    SearchResult {
+        icon_path,
+        keywords: entry.keywords.join(";"),
+        ..SearchResult::new(
+            Provider::Custom,
+            entry.name.clone(),
+            entry.description.clone(),
+            entry.command_line(),
+        )
+    }
+}
+
+/// Search all applications and collect them in a Vec of SearchResult,
+/// with the entries of the config first.
+pub fn build_cache(providers: &ProvidersConfig, entries: &[CustomEntry]) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = entries.iter().map(searchresult_from_custom).collect();
     // Build SearchResults for all desktop files we can find
-    for mut data_dir in search_dirs() {
-        data_dir.push("applications");
-        if let Ok(data_dir) = fs::read_dir(data_dir) {
-            results.append(
-                &mut data_dir
-                    .filter_map(|path| searchresult_from_desktopentry(&path.unwrap().path()))
-                    .collect(),
-            );
+    if providers.desktop_entries {
+        for mut data_dir in search_dirs() {
+            data_dir.push("applications");
+            if let Ok(data_dir) = fs::read_dir(data_dir) {
+                results.append(
+                    &mut data_dir
+                        .filter_map(|path| searchresult_from_desktopentry(&path.unwrap().path()))
+                        .collect(),
+                );
+            }
         }
     }
 
     // Now build SearchResults for all binaries we can find
-    let key = "PATH";
-    match env::var_os(key) {
-        Some(paths) => {
-            for path in env::split_paths(&paths) {
-                if let Ok(entries) = fs::read_dir(path) {
-                    results.append(
-                        &mut entries
-                            .filter_map(|path| searchresult_from_bin(&path.unwrap().path()))
-                            .collect(),
-                    );
+    if providers.binaries {
+        let key = "PATH";
+        match env::var_os(key) {
+            Some(paths) => {
+                for path in env::split_paths(&paths) {
+                    if let Ok(entries) = fs::read_dir(path) {
+                        results.append(
+                            &mut entries
+                                .filter_map(|path| searchresult_from_bin(&path.unwrap().path()))
+                                .collect(),
+                        );
+                    }
                 }
             }
+            None => println!("{} is not defined in the environment.", key),
         }
-        None => println!("{} is not defined in the environment.", key)
     }
     // That's it, return
     results
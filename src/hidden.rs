@@ -0,0 +1,24 @@
+//! Results hidden from the actions menu, kept in `$XDG_DATA_HOME/fuzzle/hidden`,
+//! one `SearchResult::id` per line.
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+
+use crate::providers::data_path;
+
+const HIDDEN_FILE: &str = "hidden";
+
+/// Ids of the hidden results
+pub fn load() -> HashSet<String> {
+    data_path(HIDDEN_FILE)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| content.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+pub fn hide(id: &str) -> io::Result<()> {
+    let path = data_path(HIDDEN_FILE)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", id)
+}
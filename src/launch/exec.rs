@@ -0,0 +1,505 @@
+//! The `Exec` key of desktop entries, and arguments typed in the query.
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::config::expand_home;
+use crate::SearchResult;
+
+/// Split a line into arguments at spaces. Double quotes group spaces in an
+/// argument, and in them `\"`, `` \` ``, `\$` and `\\` are escaped, like the
+/// desktop entry spec says. Each argument comes with whether it had quotes.
+fn tokens(line: &str) -> Vec<(String, bool)> {
+    token_spans(line)
+        .into_iter()
+        .map(|(_, token, quoted)| (token, quoted))
+        .collect()
+}
+
+/// Like `tokens`, with the range of the line each argument was read from
+fn token_spans(line: &str) -> Vec<(Range<usize>, String, bool)> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut start = None;
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        let separator = c.is_whitespace() && !in_quotes;
+        if !separator && start.is_none() {
+            start = Some(i);
+        }
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            '\\' if in_quotes => {
+                if let Some((_, escaped)) = chars.next() {
+                    token.push(escaped);
+                }
+            }
+            _ if separator => {
+                if let Some(start) = start.take() {
+                    tokens.push((start..i, std::mem::take(&mut token), quoted));
+                    quoted = false;
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if let Some(start) = start {
+        tokens.push((start..line.len(), token, quoted));
+    }
+    tokens
+}
+
+/// Split an `Exec` line into the program and its arguments, replacing the
+/// field codes: `%f` and `%u` with the first of `args` (see `exec_argvs`), `%F` and `%U` with
+/// all of them, `%c`, `%k` and `%i` with the name, desktop file and icon of
+/// `result`. Without a field code for them, `args` are appended.
+pub fn exec_argv(command: &str, result: &SearchResult, args: &[String]) -> Vec<String> {
+    let mut argv = Vec::new();
+    let mut args_used = false;
+    for (token, quoted) in tokens(command) {
+        // Field codes are not allowed in quoted arguments
+        if quoted {
+            argv.push(token);
+            continue;
+        }
+        match token.as_str() {
+            "%F" | "%U" => {
+                argv.extend_from_slice(args);
+                args_used = true;
+            }
+            "%i" => {
+                if let Some(icon) = &result.icon_path {
+                    argv.push("--icon".to_string());
+                    argv.push(icon.clone());
+                }
+            }
+            _ => {
+                let mut arg = String::new();
+                let mut chars = token.chars();
+                while let Some(c) = chars.next() {
+                    if c != '%' {
+                        arg.push(c);
+                        continue;
+                    }
+                    match chars.next() {
+                        Some('f') | Some('u') => {
+                            if let Some(first) = args.first() {
+                                arg.push_str(first);
+                            }
+                            args_used = true;
+                        }
+                        Some('c') => arg.push_str(&result.name),
+                        Some('k') => {
+                            if let Some(path) = &result.desktop_entry_path {
+                                arg.push_str(path);
+                            }
+                        }
+                        Some('%') => arg.push('%'),
+                        // Deprecated codes, and the ones that must be alone
+                        _ => (),
+                    }
+                }
+                // A code that was replaced with nothing leaves no argument
+                if !arg.is_empty() {
+                    argv.push(arg);
+                }
+            }
+        }
+    }
+    if !args_used {
+        argv.extend_from_slice(args);
+    }
+    argv
+}
+
+/// Whether an `Exec` line takes a single file or URL, with `%f` or `%u`
+fn takes_one_file(command: &str) -> bool {
+    tokens(command)
+        .iter()
+        .filter(|(_, quoted)| !quoted)
+        .any(|(token, _)| {
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                if c == '%' {
+                    if let Some('f') | Some('u') = chars.next() {
+                        return true;
+                    }
+                }
+            }
+            false
+        })
+}
+
+/// The programs to start for an `Exec` line, see `exec_argv`. A program
+/// that takes a single file is started once for each of `args`, like the
+/// desktop entry spec says.
+pub fn exec_argvs(command: &str, result: &SearchResult, args: &[String]) -> Vec<Vec<String>> {
+    if args.len() > 1 && takes_one_file(command) {
+        args.iter()
+            .map(|arg| exec_argv(command, result, std::slice::from_ref(arg)))
+            .collect()
+    } else {
+        vec![exec_argv(command, result, args)]
+    }
+}
+
+/// Quote an argument for an `Exec` line
+pub fn exec_quote(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if let '"' | '`' | '$' | '\\' = c {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Split arguments typed by the user, quoted like in `Exec` lines, with `~` expanded
+pub fn split_arguments(text: &str) -> Vec<String> {
+    tokens(text)
+        .into_iter()
+        .map(|(arg, quoted)| {
+            if quoted {
+                arg
+            } else {
+                expand_home(&arg).display().to_string()
+            }
+        })
+        .collect()
+}
+
+fn looks_like_argument(word: &str) -> bool {
+    word.starts_with(&['/', '~', '-'][..])
+        || word.starts_with("./")
+        || word.starts_with("../")
+        || word.contains("://")
+}
+
+/// Split a query into what to search for and arguments for the result.
+/// Arguments follow ` -- `, or start at the first word after the first one
+/// that looks like a path, a URL or an option: `firefox https://example.org`.
+pub fn split_query(query: &str) -> (&str, Vec<String>) {
+    let mut offset = 0;
+    for (n, word) in query.split(' ').enumerate() {
+        if n > 0 && word == "--" {
+            let args = split_arguments(&query[offset + word.len()..]);
+            return (query[..offset].trim_end(), args);
+        }
+        if n > 0 && looks_like_argument(word) {
+            return (
+                query[..offset].trim_end(),
+                split_arguments(&query[offset..]),
+            );
+        }
+        offset += word.len() + 1;
+    }
+    (query, vec![])
+}
+
+/// Quote an argument for the shell, unless it doesn't need it
+pub fn shell_quote(arg: &str) -> String {
+    let safe = |c: char| c.is_alphanumeric() || "_-./:=@%+,~".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Complete a path as far as the files in its directory agree, and end
+/// it with `/` when it's a directory
+fn complete_path(path: &str) -> Option<String> {
+    let split = path.rfind('/').map_or(0, |i| i + 1);
+    let (dir, prefix) = path.split_at(split);
+    let lookup = if dir.is_empty() {
+        PathBuf::from(".")
+    } else {
+        expand_home(dir)
+    };
+    let mut names = fs::read_dir(&lookup)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.starts_with(prefix))
+        // Hidden files only when asked for
+        .filter(|name| prefix.starts_with('.') || !name.starts_with('.'));
+    let first = names.next()?;
+    let mut common = first.clone();
+    let mut unique = true;
+    for name in names {
+        unique = false;
+        let len = common
+            .char_indices()
+            .zip(name.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8());
+        common.truncate(len);
+    }
+    let mut completed = format!("{}{}", dir, common);
+    if unique && lookup.join(&first).is_dir() {
+        completed.push('/');
+    }
+    if completed == path {
+        None
+    } else {
+        Some(completed)
+    }
+}
+
+/// Complete the last argument of a query when it's a path, in quotes if
+/// it has spaces or had them already
+pub fn complete_last_argument(query: &str) -> Option<String> {
+    let (span, word, quoted) = token_spans(query).pop()?;
+    // A space after the last argument starts the next one
+    if span.end < query.len() {
+        return None;
+    }
+    if !looks_like_argument(&word) || word.starts_with('-') || word.contains("://") {
+        return None;
+    }
+    let completed = complete_path(&word)?;
+    let completed = if quoted || completed.contains(char::is_whitespace) {
+        exec_quote(&completed)
+    } else {
+        completed
+    };
+    Some(format!("{}{}", &query[..span.start], completed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Provider;
+    use std::env;
+
+    fn strings(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    fn app(command: &str) -> SearchResult {
+        SearchResult {
+            icon_path: Some("/icons/app.png".to_string()),
+            desktop_entry_path: Some("/applications/app.desktop".to_string()),
+            ..SearchResult::new(
+                Provider::DesktopEntry,
+                "App".to_string(),
+                String::new(),
+                command.to_string(),
+            )
+        }
+    }
+
+    /// A directory of its own under the temporary directory, with these files
+    fn scratch_dir(name: &str, files: &[&str], dirs: &[&str]) -> String {
+        let dir = env::temp_dir().join(format!("fuzzle-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        for sub in dirs {
+            fs::create_dir(dir.join(sub)).unwrap();
+        }
+        dir.display().to_string()
+    }
+
+    #[test]
+    fn tokens_split_at_unquoted_spaces() {
+        assert_eq!(
+            tokens("  ls   -l \"my dir\" "),
+            vec![
+                ("ls".to_string(), false),
+                ("-l".to_string(), false),
+                ("my dir".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_unescape_in_quotes_only() {
+        assert_eq!(
+            tokens(r#""a \"b\" \$c \\d" e\f"#),
+            vec![
+                (r#"a "b" $c \d"#.to_string(), true),
+                (r"e\f".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_keep_empty_quoted_arguments() {
+        assert_eq!(
+            tokens(r#"echo "" x"#),
+            vec![
+                ("echo".to_string(), false),
+                (String::new(), true),
+                ("x".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn exec_argv_replaces_field_codes() {
+        let result = app("app --name=%c %k %i %% %d");
+        assert_eq!(
+            exec_argv(&result.command, &result, &[]),
+            strings(&[
+                "app",
+                "--name=App",
+                "/applications/app.desktop",
+                "--icon",
+                "/icons/app.png",
+                "%",
+            ])
+        );
+    }
+
+    #[test]
+    fn exec_argv_puts_arguments_in_place_of_codes() {
+        let result = app("app %U --new");
+        let args = strings(&["a.txt", "b.txt"]);
+        assert_eq!(
+            exec_argv(&result.command, &result, &args),
+            strings(&["app", "a.txt", "b.txt", "--new"])
+        );
+        let result = app("app --open=%f");
+        assert_eq!(
+            exec_argv(&result.command, &result, &args[..1]),
+            strings(&["app", "--open=a.txt"])
+        );
+    }
+
+    #[test]
+    fn exec_argv_appends_arguments_without_codes() {
+        let result = app("app --new");
+        assert_eq!(
+            exec_argv(&result.command, &result, &strings(&["a.txt"])),
+            strings(&["app", "--new", "a.txt"])
+        );
+        // A code with no arguments for it leaves nothing behind
+        let result = app("app %u");
+        assert_eq!(exec_argv(&result.command, &result, &[]), strings(&["app"]));
+    }
+
+    #[test]
+    fn exec_argv_leaves_quoted_codes_alone() {
+        let result = app(r#"sh -c "echo %f""#);
+        assert_eq!(
+            exec_argv(&result.command, &result, &[]),
+            strings(&["sh", "-c", "echo %f"])
+        );
+    }
+
+    #[test]
+    fn exec_argvs_start_one_program_per_file() {
+        let args = strings(&["a.txt", "b.txt"]);
+        let result = app("app %f");
+        assert_eq!(
+            exec_argvs(&result.command, &result, &args),
+            vec![strings(&["app", "a.txt"]), strings(&["app", "b.txt"])]
+        );
+        let result = app("app %F");
+        assert_eq!(
+            exec_argvs(&result.command, &result, &args),
+            vec![strings(&["app", "a.txt", "b.txt"])]
+        );
+        // `%%f` is a literal `%f`, not a field code
+        let result = app("app %%f");
+        assert_eq!(exec_argvs(&result.command, &result, &args).len(), 1);
+    }
+
+    #[test]
+    fn split_query_after_a_double_dash() {
+        assert_eq!(
+            split_query("vim -- a.txt \"b c\""),
+            ("vim", strings(&["a.txt", "b c"]))
+        );
+    }
+
+    #[test]
+    fn split_query_at_what_looks_like_an_argument() {
+        assert_eq!(
+            split_query("firefox https://example.org"),
+            ("firefox", strings(&["https://example.org"]))
+        );
+        assert_eq!(
+            split_query("ls -la /tmp"),
+            ("ls", strings(&["-la", "/tmp"]))
+        );
+        assert_eq!(split_query("/usr/bin/ls"), ("/usr/bin/ls", vec![]));
+        assert_eq!(split_query("text editor"), ("text editor", vec![]));
+    }
+
+    #[test]
+    fn split_query_expands_home_outside_quotes() {
+        let home = env::var("HOME").unwrap_or_default();
+        assert_eq!(
+            split_query("vim ~/a \"~/b\""),
+            ("vim", vec![format!("{}/a", home), "~/b".to_string()])
+        );
+    }
+
+    #[test]
+    fn complete_path_to_the_common_prefix() {
+        let dir = scratch_dir("common", &["report-2019.txt", "report-2020.txt"], &[]);
+        assert_eq!(
+            complete_path(&format!("{}/rep", dir)),
+            Some(format!("{}/report-20", dir))
+        );
+        // Nothing more to add
+        assert_eq!(complete_path(&format!("{}/report-20", dir)), None);
+        assert_eq!(complete_path(&format!("{}/missing", dir)), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn complete_path_ends_directories_with_a_slash() {
+        let dir = scratch_dir("dirs", &["notes"], &["music"]);
+        assert_eq!(
+            complete_path(&format!("{}/mu", dir)),
+            Some(format!("{}/music/", dir))
+        );
+        assert_eq!(
+            complete_path(&format!("{}/no", dir)),
+            Some(format!("{}/notes", dir))
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn complete_path_skips_hidden_files_unless_asked() {
+        let dir = scratch_dir("hidden", &[".config", "config.toml"], &[]);
+        assert_eq!(
+            complete_path(&format!("{}/c", dir)),
+            Some(format!("{}/config.toml", dir))
+        );
+        assert_eq!(
+            complete_path(&format!("{}/.c", dir)),
+            Some(format!("{}/.config", dir))
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn complete_last_argument_twice_through_a_space() {
+        let dir = scratch_dir("spaces", &[], &["my dir"]);
+        fs::write(format!("{}/my dir/notes.txt", dir), "").unwrap();
+        let query = complete_last_argument(&format!("vim {}/my", dir)).unwrap();
+        assert_eq!(query, format!("vim \"{}/my dir/\"", dir));
+        // The quoted path is one argument, completed further
+        assert_eq!(
+            complete_last_argument(&query),
+            Some(format!("vim \"{}/my dir/notes.txt\"", dir))
+        );
+        assert_eq!(complete_last_argument(&format!("{} ", query)), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
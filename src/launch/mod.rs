@@ -0,0 +1,222 @@
+//! Turning results into processes, and the other things that can be done with them.
+use std::env;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::state::Provider;
+use crate::SearchResult;
+
+mod exec;
+pub use exec::{
+    complete_last_argument, exec_argv, exec_argvs, exec_quote, split_arguments, split_query,
+};
+
+/// Something to do with a result, picked from its actions menu
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResultAction {
+    Launch,
+    LaunchInTerminal,
+    /// Ask for arguments, then launch with them
+    LaunchWithArguments,
+    OpenFolder,
+    CopyCommand,
+    CopyDesktopPath,
+    EditDesktopEntry,
+    Hide,
+    /// Remove a copy from the clipboard history
+    Forget,
+    ClearHistory,
+    /// An action contributed by the provider, like the `[Desktop Action]`
+    /// sections of desktop entries
+    Run {
+        name: String,
+        command: String,
+    },
+}
+
+impl ResultAction {
+    pub fn label(&self) -> String {
+        match self {
+            ResultAction::Launch => "Launch".to_string(),
+            ResultAction::LaunchInTerminal => "Launch in a terminal".to_string(),
+            ResultAction::LaunchWithArguments => "Launch with arguments…".to_string(),
+            ResultAction::OpenFolder => "Open the containing folder".to_string(),
+            ResultAction::CopyCommand => "Copy the command".to_string(),
+            ResultAction::CopyDesktopPath => "Copy the desktop file path".to_string(),
+            ResultAction::EditDesktopEntry => "Edit the desktop entry".to_string(),
+            ResultAction::Hide => "Hide from results".to_string(),
+            ResultAction::Forget => "Remove from the history".to_string(),
+            ResultAction::ClearHistory => "Clear the clipboard history".to_string(),
+            ResultAction::Run { name, .. } => name.clone(),
+        }
+    }
+}
+
+/// The actions menu of a result: what can be done with anything, then
+/// what its provider adds
+pub fn actions(result: &SearchResult) -> Vec<ResultAction> {
+    let provider = result.provider;
+    let mut actions = vec![];
+    // There's nothing to launch, only text to copy
+    if !provider.is_copied() {
+        actions.push(ResultAction::Launch);
+    }
+    if provider.takes_arguments() {
+        actions.push(ResultAction::LaunchInTerminal);
+        actions.push(ResultAction::LaunchWithArguments);
+    }
+    if provider.has_file() {
+        actions.push(ResultAction::OpenFolder);
+    }
+    actions.push(ResultAction::CopyCommand);
+    actions.append(&mut provider.actions());
+    actions.append(&mut desktop_actions(result));
+    if provider.is_cached() {
+        actions.push(ResultAction::Hide);
+    }
+    actions
+}
+
+/// The `[Desktop Action ...]` sections listed in `Actions=`
+pub fn desktop_actions(result: &SearchResult) -> Vec<ResultAction> {
+    let info = match result
+        .desktop_entry_path
+        .as_ref()
+        .and_then(|path| ini::Ini::load_from_file(path).ok())
+    {
+        Some(info) => info,
+        None => return vec![],
+    };
+    let names = info
+        .section(Some("Desktop Entry"))
+        .and_then(|section| section.get("Actions"))
+        .map(|actions| actions.to_string())
+        .unwrap_or_default();
+    names
+        .split(';')
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| info.section(Some(format!("Desktop Action {}", name))))
+        .filter_map(|section| match (section.get("Name"), section.get("Exec")) {
+            (Some(name), Some(command)) => Some(ResultAction::Run {
+                name: name.to_string(),
+                command: command.to_string(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The programs and arguments that run a result, given extra arguments.
+/// There are several when its `Exec` line takes a single file and more
+/// are given. Commands typed in the query are run by the user's shell,
+/// and results that are copied don't run anything.
+pub fn command_lines(result: &SearchResult, args: &[String]) -> Vec<Vec<String>> {
+    match result.provider {
+        Provider::DesktopEntry
+        | Provider::Custom
+        | Provider::Web
+        | Provider::Location
+        | Provider::Ssh
+        | Provider::Session => exec_argvs(&result.command, result, args),
+        Provider::Binary => {
+            let mut argv = vec![result.command.clone()];
+            argv.extend_from_slice(args);
+            vec![argv]
+        }
+        Provider::Command => {
+            let mut line = result.command.clone();
+            for arg in args {
+                line.push(' ');
+                line.push_str(&exec::shell_quote(arg));
+            }
+            vec![vec![shell(), "-c".to_string(), line]]
+        }
+        Provider::Converter | Provider::Clipboard | Provider::Character => vec![],
+    }
+}
+
+/// `$SHELL`, or `sh` if it isn't set
+pub fn shell() -> String {
+    env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| "sh".to_string())
+}
+
+/// Start a program without waiting for it, return true if it started
+pub fn spawn(argv: &[String]) -> bool {
+    match argv.split_first() {
+        Some((program, args)) => Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .spawn()
+            .is_ok(),
+        None => false,
+    }
+}
+
+/// Start each of the programs, return true if any of them started
+pub fn spawn_each(argvs: &[Vec<String>]) -> bool {
+    let started: Vec<bool> = argvs.iter().map(|argv| spawn(argv)).collect();
+    started.contains(&true)
+}
+
+/// The programs and arguments that launch a result, in a terminal if its
+/// provider runs there, see `command_lines`
+pub fn launch_argvs(terminal: &str, result: &SearchResult, args: &[String]) -> Vec<Vec<String>> {
+    let argvs = command_lines(result, args);
+    if result.provider.in_terminal() {
+        terminal_argvs(terminal, &argvs)
+    } else {
+        argvs
+    }
+}
+
+/// Launch a result with extra arguments, see `launch_argvs`
+pub fn launch(terminal: &str, result: &SearchResult, args: &[String]) -> bool {
+    spawn_each(&launch_argvs(terminal, result, args))
+}
+
+/// The commands that run each of `argvs` in a new terminal window.
+/// `terminal` is the command that runs what follows it, like `xterm -e`.
+pub fn terminal_argvs(terminal: &str, argvs: &[Vec<String>]) -> Vec<Vec<String>> {
+    argvs
+        .iter()
+        .map(|argv| {
+            let mut command: Vec<String> =
+                terminal.split_whitespace().map(str::to_string).collect();
+            command.extend_from_slice(argv);
+            command
+        })
+        .collect()
+}
+
+/// Run commands in new terminal windows, one each, see `terminal_argvs`
+pub fn in_terminal(terminal: &str, argvs: &[Vec<String>]) -> bool {
+    spawn_each(&terminal_argvs(terminal, argvs))
+}
+
+/// Open the directory of the desktop file, or of the binary
+pub fn open_folder(result: &SearchResult) -> bool {
+    let path = result
+        .desktop_entry_path
+        .clone()
+        .or_else(|| command_lines(result, &[]).into_iter().flatten().next());
+    match path.as_ref().and_then(|path| Path::new(path).parent()) {
+        Some(folder) => spawn(&["xdg-open".to_string(), folder.display().to_string()]),
+        None => false,
+    }
+}
+
+/// Edit a file with `$EDITOR` in a terminal, or the default application
+/// for it if `$EDITOR` isn't set
+pub fn edit(terminal: &str, path: &str) -> bool {
+    match env::var("EDITOR") {
+        Ok(editor) if !editor.is_empty() => {
+            let mut argv: Vec<String> = editor.split_whitespace().map(str::to_string).collect();
+            argv.push(path.to_string());
+            in_terminal(terminal, &[argv])
+        }
+        _ => spawn(&["xdg-open".to_string(), path.to_string()]),
+    }
+}
@@ -0,0 +1,14 @@
+//! fuzzle, an application launcher. The binary is in `main.rs`, the
+//! library is there so benchmarks and tests can get at the internals.
+pub mod config;
+pub mod delegate;
+pub mod dirutils;
+pub mod hidden;
+pub mod launch;
+pub mod preview;
+pub mod providers;
+pub mod search;
+pub mod state;
+pub mod widgets;
+
+pub use state::{AppState, SearchResult};
@@ -1,34 +1,100 @@
 // use druid::piet::UnitPoint;
 // use druid::widget::{Container, EnvScope, Flex, Label, List, Padding, WidgetExt};
-use druid::widget::{Flex, List, WidgetExt};
+use druid::widget::{Flex, Label, List, WidgetExt};
 // use druid::{theme, AppLauncher, Color, Env, LocalizedString, PlatformError, Widget, WindowDesc};
-use druid::{theme, AppLauncher, Color, LocalizedString, PlatformError, Widget, WindowDesc};
+use druid::{theme, AppLauncher, Env, LocalizedString, PlatformError, Widget, WindowDesc};
 
 use std::sync::Arc;
 
-mod widgets;
-use widgets::{AutoTextBox, ListElement};
+use fuzzle::config::{watch_theme, Config, DESCRIPTION_FONT_SIZE, SECONDARY_COLOR};
+use fuzzle::delegate::Delegate;
+use fuzzle::providers::clipboard;
+use fuzzle::state::Preview;
+use fuzzle::widgets::{AutoTextBox, Background, ElidedLabel, ListElement, PreviewPane};
+use fuzzle::AppState;
 
-mod state;
-use state::{AppState, SearchResult};
+/// The indicator text is this much smaller than descriptions
+const INDICATOR_TEXT_FACTOR: f64 = 0.75;
+const INDICATOR_BOTTOM: f64 = 4.;
+/// Line height of a label, relative to its font size, like druid's `Label`
+const LINE_HEIGHT_FACTOR: f64 = 1.2;
 
-mod delegate;
-use delegate::Delegate;
+/// Shows details about the selected result, and which one it is, out of how many.
+/// The details get what's left of the width, and are elided to fit.
+fn make_indicator() -> impl Widget<AppState> {
+    let label = Label::new(|data: &AppState, _env: &Env| {
+        let mode = data.match_mode.name();
+        if data.num_results == 0 {
+            format!("No results · {}", mode)
+        } else {
+            format!(
+                "{} of {} · {}",
+                data.selected_line + 1,
+                data.num_results,
+                mode
+            )
+        }
+    });
+    Flex::row()
+        .with_child(ElidedLabel::new().lens(AppState::detail), 1.)
+        .with_child(label, 0.)
+        .padding((20., 0., 20., INDICATOR_BOTTOM))
+        .env_scope(|env, _| {
+            let color = env.get(SECONDARY_COLOR);
+            let size = env.get(DESCRIPTION_FONT_SIZE) * INDICATOR_TEXT_FACTOR;
+            env.set(theme::LABEL_COLOR, color);
+            env.set(theme::TEXT_SIZE_NORMAL, size);
+        })
+}
 
-mod dirutils;
+/// Tall enough for the input and `visible_rows` results, which share the
+/// height left by the indicator a row each, and at least `window.height`
+fn window_height(config: &Config) -> f64 {
+    let theme = &config.theme;
+    let rows = (config.window.visible_rows + 1) as f64 * theme.row_height;
+    let indicator =
+        theme.description_font_size * INDICATOR_TEXT_FACTOR * LINE_HEIGHT_FACTOR + INDICATOR_BOTTOM;
+    config.window.height.max(rows + indicator)
+}
 
-fn make_ui() -> impl Widget<AppState> {
-    Flex::column()
-        .with_child(AutoTextBox::new().lens(AppState::input_text), 1.)
+fn make_ui(config: &Config) -> impl Widget<AppState> {
+    let ui = Flex::column()
+        .with_child(
+            AutoTextBox::new(Arc::new(config.keys.clone())).lens(AppState::input_text),
+            1.,
+        )
         .with_child(
             List::new(ListElement::new).lens(AppState::search_results),
-            3.,
+            config.window.visible_rows as f64,
         )
+        .with_child(make_indicator(), 0.);
+    let ui: Box<dyn Widget<AppState>> = if config.window.preview {
+        let preview = PreviewPane::new().lens(AppState::preview);
+        Box::new(
+            Flex::row()
+                .with_child(ui.fix_width(config.window.width), 0.)
+                .with_child(preview, 1.),
+        )
+    } else {
+        Box::new(ui)
+    };
+    // The theme lives in AppState so it can be swapped while running
+    Background::new(ui).env_scope(|env, data: &AppState| data.theme.apply(env))
 }
 
 fn main() -> Result<(), PlatformError> {
-    let main_window = WindowDesc::new(make_ui)
-        .window_size((550., 320.00))
+    let config = Arc::new(Config::load().unwrap_or_else(|err| {
+        eprintln!("fuzzle: {}, using default configuration", err);
+        Config::default()
+    }));
+    // Record the clipboard history instead of showing the window
+    if std::env::args().nth(1).as_deref() == Some("--clip-daemon") {
+        clipboard::daemon(&config.clipboard);
+    }
+
+    let ui_config = config.clone();
+    let main_window = WindowDesc::new(move || make_ui(&ui_config))
+        .window_size((config.window.total_width(), window_height(&config)))
         .resizable(false)
         .show_titlebar(false)
         .title(LocalizedString::new("fuzzle").with_placeholder(String::from("fuzzle")));
@@ -36,21 +102,23 @@ fn main() -> Result<(), PlatformError> {
         input_text: "".into(),
         search_results: Arc::new(vec![]),
         selected_line: 0,
+        num_results: 0,
+        scroll_offset: 0,
+        theme: Arc::new(config.theme.clone()),
+        match_mode: config.behavior.match_mode,
+        detail: "".into(),
+        preview: Arc::new(Preview::default()),
     };
 
-    AppLauncher::with_window(main_window)
-        .delegate(Delegate::new())
-        .configure_env(|env, _| {
-            env.set(theme::BORDERED_WIDGET_HEIGHT, 100.);
-            env.set(theme::TEXT_SIZE_NORMAL, 20.);
-            env.set(theme::TEXTBOX_BORDER_RADIUS, 2.);
-            env.set(
-                theme::WINDOW_BACKGROUND_COLOR,
-                Color::rgb8(0x39, 0x3d, 0x40),
-            );
-            env.set(theme::LABEL_COLOR, Color::rgb8(0xf2, 0xf2, 0xf2));
-            env.set(theme::BACKGROUND_LIGHT, Color::rgb8(0x39, 0x3d, 0x40));
-        })
+    let env_config = config.clone();
+    let launcher = AppLauncher::with_window(main_window);
+    let sink = launcher.get_external_handle();
+    if let Some(theme_path) = config.theme_path() {
+        watch_theme(theme_path, sink.clone());
+    }
+    launcher
+        .delegate(Delegate::new(config, sink))
+        .configure_env(move |env, _| env_config.configure_env(env))
         .use_simple_logger()
         .launch(data)?;
     Ok(())
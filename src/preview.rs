@@ -0,0 +1,264 @@
+//! What the preview pane shows, depending on where a result comes from.
+//!
+//! Previews read files and decode images, so they are made in a
+//! background thread and sent to the window when they are done.
+use druid::{ExtEventSink, Selector};
+use image::GenericImageView;
+use ini::Ini;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use crate::launch::{desktop_actions, shell, ResultAction};
+use crate::providers::convert;
+use crate::state::{Preview, PreviewImage, Provider};
+use crate::SearchResult;
+
+/// Sent with an `Arc<Preview>` when the preview of a result is made
+pub const PREVIEW_READY: Selector = Selector::new("fuzzle.preview-ready");
+
+/// How much of a file is read for its excerpt
+const EXCERPT_BYTES: u64 = 4096;
+const EXCERPT_LINES: usize = 20;
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "ico", "tiff", "webp"];
+/// Images are scaled down to this size when decoded, the pane is never bigger
+const THUMBNAIL_SIZE: u32 = 512;
+
+/// Handle to the thread that makes previews.
+///
+/// Only the preview of the latest result asked for is made, results
+/// selected while it was busy are skipped.
+pub struct PreviewLoader {
+    sender: Sender<SearchResult>,
+}
+
+impl PreviewLoader {
+    pub fn spawn(sink: ExtEventSink) -> Self {
+        let (sender, receiver) = channel();
+        thread::spawn(move || run(receiver, sink));
+        Self { sender }
+    }
+
+    /// Make the preview of a result, `PREVIEW_READY` is sent with it
+    pub fn load(&self, result: &SearchResult) {
+        let _ = self.sender.send(result.clone());
+    }
+}
+
+fn run(receiver: Receiver<SearchResult>, sink: ExtEventSink) {
+    while let Ok(mut result) = receiver.recv() {
+        while let Ok(newer) = receiver.try_recv() {
+            result = newer;
+        }
+        if sink
+            .submit_command(PREVIEW_READY, Arc::new(preview(&result)), None)
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// What the pane shows until the preview of a result is made
+pub fn placeholder(result: &SearchResult) -> Preview {
+    Preview {
+        key: result.id(),
+        title: result.name.clone(),
+        ..Preview::default()
+    }
+}
+
+pub fn preview(result: &SearchResult) -> Preview {
+    let mut preview = match result.provider {
+        Provider::DesktopEntry => app_preview(result),
+        Provider::Binary => file_preview(Path::new(&result.command)),
+        Provider::Command => command_preview(result),
+        Provider::Custom => custom_preview(result),
+        Provider::Location if Path::new(&result.name).exists() => {
+            file_preview(Path::new(&result.name))
+        }
+        Provider::Web | Provider::Location => Preview {
+            fields: vec![("URL".to_string(), result.description.clone())],
+            ..Preview::default()
+        },
+        Provider::Clipboard => Preview {
+            fields: vec![("Clipboard".to_string(), result.description.clone())],
+            body: excerpt(&result.command),
+            ..Preview::default()
+        },
+        Provider::Character => Preview {
+            fields: vec![
+                ("Character".to_string(), result.command.clone()),
+                ("Code".to_string(), result.description.clone()),
+            ],
+            ..Preview::default()
+        },
+        Provider::Ssh => Preview {
+            fields: vec![
+                ("Source".to_string(), result.description.clone()),
+                ("Exec".to_string(), result.command.clone()),
+            ],
+            ..Preview::default()
+        },
+        Provider::Session => Preview {
+            fields: vec![
+                ("Exec".to_string(), result.command.clone()),
+                ("Keywords".to_string(), result.keywords.replace(';', ", ")),
+            ],
+            ..Preview::default()
+        },
+        Provider::Converter => Preview {
+            fields: convert::convert(&result.description)
+                .map(|conversion| conversion.breakdown)
+                .unwrap_or_default(),
+            ..Preview::default()
+        },
+    };
+    preview.key = result.id();
+    preview.title = result.name.clone();
+    preview
+}
+
+/// Everything the desktop file says: description, categories, command and actions
+fn app_preview(result: &SearchResult) -> Preview {
+    let mut preview = Preview {
+        body: result.description.clone(),
+        ..Preview::default()
+    };
+    let mut fields = vec![("Exec".to_string(), result.command.clone())];
+    let info = result
+        .desktop_entry_path
+        .as_ref()
+        .and_then(|path| Ini::load_from_file(path).ok());
+    if let Some(section) = info.as_ref().and_then(|i| i.section(Some("Desktop Entry"))) {
+        for (key, label) in &[
+            ("GenericName", "Kind"),
+            ("Categories", "Categories"),
+            ("Keywords", "Keywords"),
+            ("Path", "Directory"),
+        ] {
+            if let Some(value) = section.get(*key) {
+                let value = value.trim_end_matches(';').replace(';', ", ");
+                fields.push((label.to_string(), value));
+            }
+        }
+        if section.get("Terminal").map(|t| t.to_string()) == Some("true".to_string()) {
+            fields.push(("Terminal".to_string(), "yes".to_string()));
+        }
+    }
+    for action in desktop_actions(result) {
+        if let ResultAction::Run { name, command } = action {
+            fields.push((format!("Action: {}", name), command));
+        }
+    }
+    preview.fields = fields;
+    preview
+}
+
+/// The shell that runs the command, and the command itself
+fn command_preview(result: &SearchResult) -> Preview {
+    Preview {
+        fields: vec![("Shell".to_string(), shell())],
+        body: result.command.clone(),
+        ..Preview::default()
+    }
+}
+
+/// What the entry from the config runs, and the keywords it has
+fn custom_preview(result: &SearchResult) -> Preview {
+    let mut fields = vec![("Exec".to_string(), result.command.clone())];
+    if !result.keywords.is_empty() {
+        fields.push(("Keywords".to_string(), result.keywords.replace(';', ", ")));
+    }
+    Preview {
+        fields,
+        body: result.description.clone(),
+        ..Preview::default()
+    }
+}
+
+/// The start of text files, a thumbnail for images, and the size of anything else
+fn file_preview(path: &Path) -> Preview {
+    let mut preview = Preview::default();
+    preview
+        .fields
+        .push(("Path".to_string(), path.display().to_string()));
+    if let Ok(metadata) = fs::metadata(path) {
+        preview
+            .fields
+            .push(("Size".to_string(), format_size(metadata.len())));
+    }
+
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    if extension.is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.as_str())) {
+        preview.image = thumbnail(path);
+        return preview;
+    }
+
+    if let Some(text) = text_head(path, EXCERPT_BYTES) {
+        if text.starts_with("#!") {
+            let interpreter = text.lines().next().unwrap_or("")[2..].trim().to_string();
+            preview
+                .fields
+                .push(("Interpreter".to_string(), interpreter));
+        }
+        preview.body = excerpt(&text);
+    }
+    preview
+}
+
+/// The first `bytes` of a file, if it's text as far as can be told from
+/// them: there's no NUL byte in them
+pub fn text_head(path: &Path, bytes: u64) -> Option<String> {
+    let mut head = Vec::new();
+    fs::File::open(path)
+        .and_then(|file| file.take(bytes).read_to_end(&mut head))
+        .ok()?;
+    if head.contains(&0) {
+        None
+    } else {
+        Some(String::from_utf8_lossy(&head).into_owned())
+    }
+}
+
+/// Decode an image, scaled down to fit the pane
+fn thumbnail(path: &Path) -> Option<PreviewImage> {
+    let im = image::open(path)
+        .ok()?
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    let (width, height) = im.dimensions();
+    Some(PreviewImage {
+        data: Arc::new(im.to_rgba().into_raw()),
+        width: width as usize,
+        height: height as usize,
+    })
+}
+
+/// The first lines of a text
+fn excerpt(text: &str) -> String {
+    text.lines()
+        .take(EXCERPT_LINES)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024. && unit < units.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
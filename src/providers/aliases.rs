@@ -0,0 +1,51 @@
+//! Short queries that go straight to a result, above everything that matches.
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::SearchResult;
+
+pub struct Aliases {
+    /// Alias to the name or desktop file name of the result, lowercase
+    targets: HashMap<String, String>,
+    results: HashMap<String, SearchResult>,
+}
+
+impl Aliases {
+    pub fn new(aliases: &HashMap<String, String>) -> Self {
+        Self {
+            targets: aliases
+                .iter()
+                .map(|(alias, target)| (alias.to_string(), target.to_lowercase()))
+                .collect(),
+            results: HashMap::new(),
+        }
+    }
+
+    /// Find the result of every alias. Names are preferred over desktop file names.
+    pub fn set_cache(&mut self, cache: &[SearchResult]) {
+        self.results.clear();
+        for (alias, target) in &self.targets {
+            let by_name = cache.iter().find(|r| r.name.to_lowercase() == *target);
+            let by_file = || {
+                cache.iter().find(|r| {
+                    let file = r.desktop_entry_path.as_ref().map(Path::new);
+                    let stem = file.and_then(|f| f.file_stem()).and_then(|s| s.to_str());
+                    let name = file.and_then(|f| f.file_name()).and_then(|s| s.to_str());
+                    stem.map(str::to_lowercase).as_ref() == Some(target)
+                        || name.map(str::to_lowercase).as_ref() == Some(target)
+                })
+            };
+            match by_name.or_else(by_file) {
+                Some(result) => {
+                    self.results.insert(alias.clone(), result.clone());
+                }
+                None => eprintln!("fuzzle: nothing found for alias \"{}\"", alias),
+            }
+        }
+    }
+
+    /// The result of a query that is an alias
+    pub fn result(&self, query: &str) -> Option<SearchResult> {
+        self.results.get(query.trim()).cloned()
+    }
+}
@@ -0,0 +1,194 @@
+//! Emoji and other characters, found by `:shortcode`, by `u+` code point
+//! or by name, and copied on Enter.
+//!
+//! The table in `assets/characters.tsv` has a character, its name and its
+//! shortcodes on each line, separated by tabs. Emoji come first, with
+//! their CLDR names and gemoji shortcodes, then symbols with their
+//! Unicode names. See `assets/LICENSE-UNICODE` and `assets/LICENSE-GEMOJI`.
+
+use crate::state::Provider;
+use crate::SearchResult;
+
+const TABLE: &str = include_str!("../assets/characters.tsv");
+const SHORTCODE_PREFIX: char = ':';
+const MAX_SHORTCODE_RESULTS: usize = 50;
+/// Searching by name only starts with this many chars, and only shows a
+/// few results, below the search results
+const MIN_NAME_CHARS: usize = 3;
+const MAX_NAME_RESULTS: usize = 5;
+
+struct Character {
+    glyph: &'static str,
+    name: &'static str,
+    /// The name in lower case, to search
+    folded: String,
+    shortcodes: Vec<&'static str>,
+}
+
+pub struct Characters {
+    table: Vec<Character>,
+    /// Every word of every folded name with the position of its
+    /// character in `table`, sorted to find the words with a prefix
+    name_words: Vec<(String, usize)>,
+}
+
+/// The words of a name, as the words of a query are matched against them
+fn name_words(name: &str) -> impl Iterator<Item = &str> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+}
+
+/// Whether every word of the query starts a word of the name
+fn name_matches(name: &str, words: &[String]) -> bool {
+    words
+        .iter()
+        .all(|word| name_words(name).any(|part| part.starts_with(word.as_str())))
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Like `U+1F44D`, with a code point for each char of the glyph
+fn code_points(glyph: &str) -> String {
+    glyph
+        .chars()
+        .map(|c| format!("U+{:04X}", c as u32))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn result(glyph: &str, name: &str, shortcodes: &[&str]) -> SearchResult {
+    let mut description = vec![code_points(glyph)];
+    description.extend(shortcodes.iter().map(|code| format!(":{}:", code)));
+    SearchResult::new(
+        Provider::Character,
+        name.to_string(),
+        description.join(" · "),
+        glyph.to_string(),
+    )
+}
+
+impl Characters {
+    pub fn new() -> Self {
+        let table: Vec<Character> = TABLE
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let glyph = fields.next()?;
+                let name = fields.next()?;
+                let shortcodes = fields.next().unwrap_or("");
+                Some(Character {
+                    glyph,
+                    name,
+                    folded: name.to_lowercase(),
+                    shortcodes: shortcodes.split_whitespace().collect(),
+                })
+            })
+            .collect();
+        let mut name_words: Vec<(String, usize)> = table
+            .iter()
+            .enumerate()
+            .flat_map(|(i, c)| name_words(&c.folded).map(move |word| (word.to_string(), i)))
+            .collect();
+        name_words.sort_unstable();
+        Self { table, name_words }
+    }
+
+    fn result(&self, character: &Character) -> SearchResult {
+        result(character.glyph, character.name, &character.shortcodes)
+    }
+
+    /// The characters for a query like `:thumb`: the one with that
+    /// shortcode, those with a shortcode that starts with it, then those
+    /// with a name that has its words
+    pub fn shortcode(&self, query: &str) -> Option<Vec<SearchResult>> {
+        let query = query.trim();
+        if !query.starts_with(SHORTCODE_PREFIX) {
+            return None;
+        }
+        let code = query[1..].trim_end_matches(SHORTCODE_PREFIX).to_lowercase();
+        if code.is_empty() {
+            return Some(vec![]);
+        }
+        let words = words(&code);
+        let rank = |c: &Character| {
+            if c.shortcodes.contains(&code.as_str()) {
+                Some(0)
+            } else if c.shortcodes.iter().any(|s| s.starts_with(&code)) {
+                Some(1)
+            } else if name_matches(&c.folded, &words) {
+                Some(2)
+            } else {
+                None
+            }
+        };
+        let mut found: Vec<(usize, &Character)> = self
+            .table
+            .iter()
+            .filter_map(|c| rank(c).map(|rank| (rank, c)))
+            .collect();
+        found.sort_by_key(|(rank, _)| *rank);
+        Some(
+            found
+                .into_iter()
+                .take(MAX_SHORTCODE_RESULTS)
+                .map(|(_, c)| self.result(c))
+                .collect(),
+        )
+    }
+
+    /// The character of a query like `u+2192`
+    pub fn code_point(&self, query: &str) -> Option<SearchResult> {
+        let query = query.trim();
+        if !(query.starts_with("u+") || query.starts_with("U+")) {
+            return None;
+        }
+        let value = u32::from_str_radix(&query[2..], 16).ok()?;
+        let c = std::char::from_u32(value).filter(|c| !c.is_control())?;
+        let glyph = c.to_string();
+        Some(match self.table.iter().find(|c| c.glyph == glyph) {
+            Some(character) => self.result(character),
+            None => result(&glyph, &code_points(&glyph), &[]),
+        })
+    }
+
+    /// A few characters with a name that has the words of the query,
+    /// shortest names first
+    pub fn by_name(&self, query: &str) -> Vec<SearchResult> {
+        if query.trim().chars().count() < MIN_NAME_CHARS {
+            return vec![];
+        }
+        let words = words(query);
+        // Only the names with a word that starts with the longest word of
+        // the query can match
+        let longest = match words.iter().max_by_key(|word| word.len()) {
+            Some(word) => word.as_str(),
+            None => return vec![],
+        };
+        let start = self
+            .name_words
+            .partition_point(|(word, _)| word.as_str() < longest);
+        let mut candidates: Vec<usize> = self.name_words[start..]
+            .iter()
+            .take_while(|(word, _)| word.starts_with(longest))
+            .map(|(_, i)| *i)
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        let mut found: Vec<&Character> = candidates
+            .into_iter()
+            .map(|i| &self.table[i])
+            .filter(|c| name_matches(&c.folded, &words))
+            .collect();
+        found.sort_by_key(|c| c.name.len());
+        found
+            .into_iter()
+            .take(MAX_NAME_RESULTS)
+            .map(|c| self.result(c))
+            .collect()
+    }
+}
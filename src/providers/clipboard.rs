@@ -0,0 +1,233 @@
+//! The clipboard history. `fuzzle --clip-daemon` records the text that's
+//! copied, and queries starting with `clip` search it, most recent first.
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, OpenOptions, Permissions};
+use std::io::{self, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::data_path;
+use crate::config::ClipboardConfig;
+use crate::search::{highlight, CaseMatching, MatchMode};
+use crate::state::Provider;
+use crate::SearchResult;
+
+/// Queries for the history start with this, see `keyword_terms`
+pub const PREFIX: &str = "clip";
+const HISTORY_FILE: &str = "clipboard";
+/// Names are the text on one line, cut after this many chars
+const MAX_NAME_CHARS: usize = 500;
+/// Password managers offer this type along with the secrets they copy
+const SECRET_HINT: &str = "x-kde-passwordManagerHint";
+/// The types text can be read as, best first
+const TEXT_TYPES: &[&str] = &[
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "text/plain",
+    "STRING",
+    "TEXT",
+];
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    text: String,
+    /// When it was copied, as a unix timestamp
+    time: i64,
+}
+
+#[derive(Default)]
+pub struct Clipboard {
+    /// Oldest first, without duplicates
+    history: Vec<Entry>,
+}
+
+fn load() -> Vec<Entry> {
+    data_path(HISTORY_FILE)
+        .ok()
+        .and_then(|path| fs::File::open(path).ok())
+        .and_then(|file| bincode::deserialize_from(file).ok())
+        .unwrap_or_default()
+}
+
+/// Replace the history file. Only the user can read it, and it's never
+/// seen half written.
+fn save(history: &[Entry]) -> io::Result<()> {
+    let path = data_path(HISTORY_FILE)?;
+    let partial = path.with_extension("partial");
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&partial)?;
+    // The mode is only set on files it creates, not on one left behind
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    bincode::serialize_into(file, history).map_err(|e| io::Error::other(e.to_string()))?;
+    fs::rename(partial, path)
+}
+
+/// Run a program and return what it printed, if it succeeded
+fn output(argv: &[&str]) -> Option<Vec<u8>> {
+    Command::new(argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| output.stdout)
+}
+
+/// The text in the clipboard, with `wl-paste` on Wayland and `xclip` on X.
+/// Secrets, copies bigger than `max_size` and anything that isn't text are
+/// left out.
+fn read_clipboard(max_size: usize) -> Option<String> {
+    let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+    let types = if wayland {
+        output(&["wl-paste", "--list-types"])?
+    } else {
+        output(&["xclip", "-selection", "clipboard", "-o", "-t", "TARGETS"])?
+    };
+    let types = String::from_utf8_lossy(&types);
+    let types: Vec<&str> = types.lines().map(str::trim).collect();
+    if types.iter().any(|t| t.ends_with(SECRET_HINT)) {
+        return None;
+    }
+    let text_type = TEXT_TYPES.iter().find(|t| types.contains(t))?;
+    let text = if wayland {
+        output(&["wl-paste", "--no-newline", "--type", text_type])?
+    } else {
+        output(&["xclip", "-selection", "clipboard", "-o", "-t", text_type])?
+    };
+    if text.len() > max_size {
+        return None;
+    }
+    String::from_utf8(text)
+        .ok()
+        .filter(|text| !text.trim().is_empty())
+}
+
+/// Put text in the clipboard with `wl-copy` on Wayland and `xclip` on X.
+/// They keep it there after fuzzle exits. Return false if that failed.
+pub fn copy(text: &str) -> bool {
+    let argv: &[&str] = if env::var_os("WAYLAND_DISPLAY").is_some() {
+        &["wl-copy"]
+    } else {
+        &["xclip", "-i", "-selection", "clipboard"]
+    };
+    let mut child = match Command::new(argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => return false,
+    };
+    // Both fork once they read the text, and the child serves it
+    let written = child
+        .stdin
+        .take()
+        .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+    written && child.wait().is_ok_and(|status| status.success())
+}
+
+/// Put a copy at the end of the history file. It's read again first,
+/// fuzzle may have changed it.
+fn record(text: String, config: &ClipboardConfig) -> io::Result<()> {
+    let mut history = load();
+    history.retain(|entry| entry.text != text);
+    history.push(Entry {
+        text,
+        time: Local::now().timestamp(),
+    });
+    let excess = history.len().saturating_sub(config.max_entries);
+    history.drain(..excess);
+    save(&history)
+}
+
+/// Watch the clipboard and record what's copied, until killed
+pub fn daemon(config: &ClipboardConfig) -> ! {
+    let interval = Duration::from_millis(config.poll_interval_ms);
+    let mut last: Option<String> = None;
+    loop {
+        if let Some(text) = read_clipboard(config.max_size) {
+            if last.as_ref() != Some(&text) {
+                if let Err(e) = record(text.clone(), config) {
+                    eprintln!("fuzzle: can't save the clipboard history: {}", e);
+                }
+                last = Some(text);
+            }
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// Like `Copied 2020-05-04 12:30 · 3 lines`
+fn describe(entry: &Entry) -> String {
+    let mut parts = vec![];
+    if let Some(time) = Local.timestamp_opt(entry.time, 0).single() {
+        parts.push(format!("Copied {}", time.format("%Y-%m-%d %H:%M")));
+    }
+    let lines = entry.text.lines().count();
+    if lines > 1 {
+        parts.push(format!("{} lines", lines));
+    }
+    parts.push(format!("{} chars", entry.text.chars().count()));
+    parts.join(" · ")
+}
+
+impl Clipboard {
+    pub fn new() -> Self {
+        Self { history: load() }
+    }
+
+    /// The copies that match the terms, most recent first
+    pub fn results(&self, terms: &str) -> Vec<SearchResult> {
+        self.history
+            .iter()
+            .rev()
+            .filter_map(|entry| {
+                let words: Vec<&str> = entry.text.split_whitespace().collect();
+                let mut name: String = words.join(" ").chars().take(MAX_NAME_CHARS).collect();
+                let indices = highlight(&name, terms, MatchMode::Fuzzy, false, CaseMatching::Smart);
+                if !terms.is_empty() && indices.is_empty() {
+                    return None;
+                }
+                if name.len() < entry.text.len() && name.chars().count() == MAX_NAME_CHARS {
+                    name.push('…');
+                }
+                Some(SearchResult {
+                    indices: Arc::new(indices),
+                    ..SearchResult::new(
+                        Provider::Clipboard,
+                        name,
+                        describe(entry),
+                        entry.text.clone(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Remove a copy from the history
+    pub fn forget(&mut self, text: &str) {
+        self.history.retain(|entry| entry.text != text);
+        if let Err(e) = save(&self.history) {
+            eprintln!("fuzzle: can't save the clipboard history: {}", e);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+        if let Err(e) = save(&self.history) {
+            eprintln!("fuzzle: can't save the clipboard history: {}", e);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Dates and times: `now + 3 days`, `2024-12-25 - today`, `unix 1700000000`.
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeDelta, TimeZone};
+
+use super::Conversion;
+
+const DATE: &str = "%Y-%m-%d";
+const DATE_TIME: &str = "%Y-%m-%d %H:%M";
+
+/// A point in time, and whether its time of the day matters
+struct Moment {
+    time: DateTime<Local>,
+    has_time: bool,
+}
+
+fn midnight(date: NaiveDate) -> Option<DateTime<Local>> {
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .single()
+}
+
+fn moment(text: &str) -> Option<Moment> {
+    let text = text.trim();
+    let today = Local::now().date_naive();
+    let day = |days: i64| {
+        Some(Moment {
+            time: midnight(today.checked_add_signed(TimeDelta::try_days(days)?)?)?,
+            has_time: false,
+        })
+    };
+    match text {
+        "now" => {
+            return Some(Moment {
+                time: Local::now(),
+                has_time: true,
+            })
+        }
+        "today" => return day(0),
+        "tomorrow" => return day(1),
+        "yesterday" => return day(-1),
+        _ => (),
+    }
+    for format in &["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(Moment {
+                time: Local.from_local_datetime(&time).single()?,
+                has_time: true,
+            });
+        }
+    }
+    let date = NaiveDate::parse_from_str(text, DATE).ok()?;
+    Some(Moment {
+        time: midnight(date)?,
+        has_time: false,
+    })
+}
+
+/// A duration like `3 days`, `2h` or `1 week 2 days`, and whether it has
+/// units shorter than a day. None if it's too long to be represented.
+fn duration(text: &str) -> Option<(TimeDelta, bool)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut pos = 0;
+    let mut total = TimeDelta::zero();
+    let mut has_time = false;
+    let skip_spaces = |pos: &mut usize| {
+        while chars.get(*pos).is_some_and(|c| c.is_whitespace()) {
+            *pos += 1;
+        }
+    };
+    skip_spaces(&mut pos);
+    if pos == chars.len() {
+        return None;
+    }
+    while pos < chars.len() {
+        let digits = chars[pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        let amount: i64 = chars[pos..pos + digits]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()?;
+        pos += digits;
+        skip_spaces(&mut pos);
+        let letters = chars[pos..]
+            .iter()
+            .take_while(|c| c.is_alphabetic())
+            .count();
+        let unit: String = chars[pos..pos + letters].iter().collect();
+        pos += letters;
+        skip_spaces(&mut pos);
+        let (length, is_time) = match unit.as_str() {
+            "s" | "sec" | "second" | "seconds" => (TimeDelta::try_seconds(amount)?, true),
+            "m" | "min" | "minute" | "minutes" => (TimeDelta::try_minutes(amount)?, true),
+            "h" | "hour" | "hours" => (TimeDelta::try_hours(amount)?, true),
+            "d" | "day" | "days" => (TimeDelta::try_days(amount)?, false),
+            "w" | "week" | "weeks" => (TimeDelta::try_weeks(amount)?, false),
+            _ => return None,
+        };
+        total = total.checked_add(&length)?;
+        has_time |= is_time;
+    }
+    Some((total, has_time))
+}
+
+/// Like `3 days, 4 hours`, with the two largest units that aren't zero,
+/// to the nearest minute
+fn describe(duration: TimeDelta) -> String {
+    let minutes = (duration.num_seconds() + 30).div_euclid(60);
+    let parts = [
+        (minutes / 1440, "day"),
+        (minutes / 60 % 24, "hour"),
+        (minutes % 60, "minute"),
+    ];
+    let parts: Vec<String> = parts
+        .iter()
+        .filter(|(amount, _)| *amount != 0)
+        .take(2)
+        .map(|(amount, unit)| {
+            let plural = if amount.abs() == 1 { "" } else { "s" };
+            format!("{} {}{}", amount.abs(), unit, plural)
+        })
+        .collect();
+    if parts.is_empty() {
+        "less than a minute".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Dates formatted every way they might be needed
+fn conversion(moment: Moment) -> Conversion {
+    let format = if moment.has_time { DATE_TIME } else { DATE };
+    let from_now = moment.time.signed_duration_since(Local::now());
+    let relative = if from_now >= TimeDelta::zero() {
+        format!("in {}", describe(from_now))
+    } else {
+        format!("{} ago", describe(-from_now))
+    };
+    Conversion {
+        answer: moment.time.format(format).to_string(),
+        breakdown: vec![
+            (
+                "Day".to_string(),
+                moment.time.format("%A %-d %B %Y").to_string(),
+            ),
+            (
+                "ISO 8601".to_string(),
+                moment.time.to_rfc3339_opts(SecondsFormat::Secs, false),
+            ),
+            ("Unix time".to_string(), moment.time.timestamp().to_string()),
+            ("From now".to_string(), relative),
+        ],
+    }
+}
+
+/// The terms of `a + b - c`, each with the sign before it, 1 for the first
+fn signed_terms(text: &str) -> Vec<(i32, &str)> {
+    let mut terms = vec![];
+    let mut sign = 1;
+    let mut rest = text;
+    loop {
+        let next = [(" + ", 1), (" - ", -1)]
+            .iter()
+            .filter_map(|(operator, sign)| rest.find(operator).map(|i| (i, *sign)))
+            .min();
+        match next {
+            Some((i, next_sign)) => {
+                terms.push((sign, &rest[..i]));
+                sign = next_sign;
+                rest = &rest[i + 3..];
+            }
+            None => {
+                terms.push((sign, rest));
+                return terms;
+            }
+        }
+    }
+}
+
+pub fn convert(query: &str) -> Option<Conversion> {
+    if let Some(seconds) = query.strip_prefix("unix ") {
+        let seconds: i64 = seconds.trim().parse().ok()?;
+        let time = Local.timestamp_opt(seconds, 0).single()?;
+        let has_time = true;
+        return Some(conversion(Moment { time, has_time }));
+    }
+    if let Some(time) = query.strip_suffix(" in unix") {
+        let moment = moment(time)?;
+        let mut conversion = conversion(moment);
+        conversion.answer = conversion.breakdown[2].1.clone();
+        return Some(conversion);
+    }
+    if let Some(moment) = moment(query) {
+        return Some(conversion(moment));
+    }
+    // A date plus or minus durations, or the time between two dates
+    let terms = signed_terms(query);
+    let start = match terms.split_first() {
+        Some(((_, start), rest)) if !rest.is_empty() => moment(start)?,
+        _ => return None,
+    };
+    if let [_, (-1, end)] = terms[..] {
+        if let Some(end) = moment(end) {
+            let between = start.time.signed_duration_since(end.time);
+            return Some(Conversion {
+                answer: describe(between),
+                breakdown: vec![
+                    ("Days".to_string(), between.num_days().to_string()),
+                    ("Hours".to_string(), between.num_hours().to_string()),
+                    ("Seconds".to_string(), between.num_seconds().to_string()),
+                ],
+            });
+        }
+    }
+    // Durations can be chained: `now + 1 week - 2 days`
+    let mut time = start.time;
+    let mut has_time = start.has_time;
+    for (sign, term) in &terms[1..] {
+        let (length, is_time) = duration(term)?;
+        time = if *sign > 0 {
+            time.checked_add_signed(length)?
+        } else {
+            time.checked_sub_signed(length)?
+        };
+        has_time |= is_time;
+    }
+    Some(conversion(Moment { time, has_time }))
+}
@@ -0,0 +1,226 @@
+//! Arithmetic expressions, and integers in other bases.
+
+/// Read a number at the start of some chars: decimal with an optional
+/// exponent, or an integer with a `0x`, `0o` or `0b` prefix. Returns the
+/// number and how many chars it took.
+pub fn number_prefix(chars: &[char]) -> Option<(f64, usize)> {
+    let radix = match (chars.first(), chars.get(1)) {
+        (Some('0'), Some('x')) | (Some('0'), Some('X')) => Some(16),
+        (Some('0'), Some('o')) | (Some('0'), Some('O')) => Some(8),
+        (Some('0'), Some('b')) | (Some('0'), Some('B')) => Some(2),
+        _ => None,
+    };
+    let digits = radix.map_or(0, |radix| {
+        chars[2..].iter().take_while(|c| c.is_digit(radix)).count()
+    });
+    // `0b` alone is zero bytes, not a binary number
+    if let (Some(radix), true) = (radix, digits > 0) {
+        let text: String = chars[2..2 + digits].iter().collect();
+        let value = i64::from_str_radix(&text, radix).ok()?;
+        return Some((value as f64, 2 + digits));
+    }
+    let mut len = chars
+        .iter()
+        .take_while(|c| c.is_ascii_digit() || **c == '.')
+        .count();
+    // An exponent, only when digits follow, `2e` is not a number
+    if let Some('e') | Some('E') = chars.get(len) {
+        let sign = match chars.get(len + 1) {
+            Some('+') | Some('-') => 1,
+            _ => 0,
+        };
+        let digits = chars[len + 1 + sign..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if digits > 0 && len > 0 {
+            len += 1 + sign + digits;
+        }
+    }
+    let text: String = chars[..len].iter().collect();
+    text.parse().ok().map(|value| (value, len))
+}
+
+/// A whole text that is a number, like `0x1F` or `-2.5`
+pub fn number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let negative = text.starts_with('-');
+    let text = if negative { &text[1..] } else { text };
+    let chars: Vec<char> = text.chars().collect();
+    match number_prefix(&chars) {
+        Some((value, len)) if len == chars.len() => Some(if negative { -value } else { value }),
+        _ => None,
+    }
+}
+
+/// Recursive descent over the chars of an expression
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// Operators and functions seen, a lone number is not a calculation
+    operations: usize,
+}
+
+impl Parser {
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// expression := term (("+" | "-") term)*
+    fn expression(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Some(value);
+            }
+            self.operations += 1;
+        }
+    }
+
+    /// term := power (("*" | "/" | "%") power)*
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.power()?;
+        loop {
+            if self.peek() == Some('*') && self.chars.get(self.pos + 1) != Some(&'*') {
+                self.pos += 1;
+                value *= self.power()?;
+            } else if self.eat('/') {
+                value /= self.power()?;
+            } else if self.eat('%') {
+                value %= self.power()?;
+            } else {
+                return Some(value);
+            }
+            self.operations += 1;
+        }
+    }
+
+    /// power := unary (("^" | "**") power)?
+    fn power(&mut self) -> Option<f64> {
+        let base = self.unary()?;
+        let is_power = if self.eat('^') {
+            true
+        } else if self.peek() == Some('*') && self.chars.get(self.pos + 1) == Some(&'*') {
+            self.pos += 2;
+            true
+        } else {
+            false
+        };
+        if is_power {
+            self.operations += 1;
+            Some(base.powf(self.power()?))
+        } else {
+            Some(base)
+        }
+    }
+
+    /// unary := "-" unary | atom
+    fn unary(&mut self) -> Option<f64> {
+        if self.eat('-') {
+            return self.unary().map(|value| -value);
+        }
+        self.atom()
+    }
+
+    /// atom := number | "(" expression ")" | constant | function "(" expression ")"
+    fn atom(&mut self) -> Option<f64> {
+        let c = self.peek()?;
+        if self.eat('(') {
+            let value = self.expression()?;
+            return if self.eat(')') { Some(value) } else { None };
+        }
+        if c.is_ascii_digit() || c == '.' {
+            let (value, len) = number_prefix(&self.chars[self.pos..])?;
+            self.pos += len;
+            return Some(value);
+        }
+        let len = self.chars[self.pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .count();
+        let name: String = self.chars[self.pos..self.pos + len].iter().collect();
+        self.pos += len;
+        match name.as_str() {
+            "pi" => return Some(std::f64::consts::PI),
+            "e" => return Some(std::f64::consts::E),
+            _ => (),
+        }
+        let function: fn(f64) -> f64 = match name.as_str() {
+            "sqrt" => f64::sqrt,
+            "abs" => f64::abs,
+            "round" => f64::round,
+            "floor" => f64::floor,
+            "ceil" => f64::ceil,
+            "ln" => f64::ln,
+            "log" => f64::log10,
+            "sin" => f64::sin,
+            "cos" => f64::cos,
+            "tan" => f64::tan,
+            _ => return None,
+        };
+        if !self.eat('(') {
+            return None;
+        }
+        let value = self.expression()?;
+        if !self.eat(')') {
+            return None;
+        }
+        self.operations += 1;
+        Some(function(value))
+    }
+}
+
+/// The value of an expression, if it's one. `strict` requires an
+/// operator or a function, so that a lone number isn't a calculation.
+pub fn evaluate(text: &str, strict: bool) -> Option<f64> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        operations: 0,
+    };
+    let value = parser.expression()?;
+    let finished = parser.peek().is_none();
+    if !finished || (strict && parser.operations == 0) || !value.is_finite() {
+        return None;
+    }
+    Some(value)
+}
+
+/// The radix of a base name, like `hex`
+pub fn radix(name: &str) -> Option<u32> {
+    match name.to_lowercase().as_str() {
+        "dec" | "decimal" => Some(10),
+        "hex" | "hexadecimal" => Some(16),
+        "oct" | "octal" => Some(8),
+        "bin" | "binary" => Some(2),
+        _ => None,
+    }
+}
+
+/// An integer written in a base, with the prefix of the base
+pub fn in_radix(value: i64, radix: u32) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let value = (value as i128).abs();
+    match radix {
+        16 => format!("{}0x{:X}", sign, value),
+        8 => format!("{}0o{:o}", sign, value),
+        2 => format!("{}0b{:b}", sign, value),
+        _ => format!("{}{}", sign, value),
+    }
+}
@@ -0,0 +1,241 @@
+//! Answers computed from the query: units, bases, dates and arithmetic.
+//! Like `10 km in mi`, `0x1F in dec`, `now + 3 days` or `2 ^ 10`.
+
+use crate::state::Provider;
+use crate::SearchResult;
+
+mod dates;
+mod expr;
+mod units;
+
+/// The answer to a query, and the details shown in the preview
+pub struct Conversion {
+    pub answer: String,
+    pub breakdown: Vec<(String, String)>,
+}
+
+/// Numbers as they'd be written: no trailing zeros, and an exponent when
+/// they're very big or very small
+fn format_number(value: f64) -> String {
+    let magnitude = value.abs();
+    if magnitude != 0. && !(1e-4..1e15).contains(&magnitude) {
+        return format!("{:e}", value);
+    }
+    if value.fract() == 0. {
+        return format!("{}", value as i64);
+    }
+    let text = format!("{:.6}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Split `<value> in <target>` at the last `in`, `to` or `as`
+fn split_target(query: &str) -> Option<(&str, &str)> {
+    [" in ", " to ", " as "]
+        .iter()
+        .filter_map(|word| query.rfind(word).map(|i| (i, word.len())))
+        .max()
+        .map(|(i, len)| (query[..i].trim(), query[i + len..].trim()))
+        .filter(|(value, target)| !value.is_empty() && !target.is_empty())
+}
+
+/// An integer written in another base, with all the common bases listed
+fn convert_radix(value: &str, radix: u32) -> Option<Conversion> {
+    let value = expr::number(value).or_else(|| expr::evaluate(value, false))?;
+    if value.fract() != 0. || value.abs() > i64::MAX as f64 {
+        return None;
+    }
+    let value = value as i64;
+    let breakdown = [
+        ("Decimal", 10),
+        ("Hexadecimal", 16),
+        ("Octal", 8),
+        ("Binary", 2),
+    ]
+    .iter()
+    .map(|(name, radix)| (name.to_string(), expr::in_radix(value, *radix)))
+    .collect();
+    Some(Conversion {
+        answer: expr::in_radix(value, radix),
+        breakdown,
+    })
+}
+
+fn convert_units(value: &str, target: &str) -> Option<Conversion> {
+    let (amount, from) = units::quantity(value)?;
+    let to = units::find(target)?;
+    if from.dimension != to.dimension {
+        return None;
+    }
+    let converted = to.to_unit(from.to_base(amount));
+    Some(Conversion {
+        answer: format!("{} {}", format_number(converted), to.name()),
+        breakdown: vec![
+            (
+                "From".to_string(),
+                format!("{} {}", format_number(amount), from.name()),
+            ),
+            ("Kind".to_string(), from.dimension.name().to_string()),
+        ],
+    })
+}
+
+/// What a query computes to, if it's something to compute
+pub fn convert(query: &str) -> Option<Conversion> {
+    let query = query.trim();
+    if query.is_empty() {
+        return None;
+    }
+    if let Some(conversion) = dates::convert(query) {
+        return Some(conversion);
+    }
+    if let Some((value, target)) = split_target(query) {
+        let conversion = match expr::radix(target) {
+            Some(radix) => convert_radix(value, radix),
+            None => convert_units(value, target),
+        };
+        if conversion.is_some() {
+            return conversion;
+        }
+    }
+    let value = expr::evaluate(query, true)?;
+    Some(Conversion {
+        answer: format_number(value),
+        breakdown: vec![("Expression".to_string(), query.to_string())],
+    })
+}
+
+/// The answer as a result, Enter copies it
+pub fn result(query: &str) -> Option<SearchResult> {
+    let conversion = convert(query)?;
+    Some(SearchResult::new(
+        Provider::Converter,
+        conversion.answer.clone(),
+        query.trim().to_string(),
+        conversion.answer,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn answer(query: &str) -> Option<String> {
+        convert(query).map(|conversion| conversion.answer)
+    }
+
+    /// A line of the breakdown of a query
+    fn detail(query: &str, label: &str) -> Option<String> {
+        convert(query)?
+            .breakdown
+            .into_iter()
+            .find(|(name, _)| name == label)
+            .map(|(_, value)| value)
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(answer("10 km in mi"), Some("6.213712 mi".to_string()));
+        assert_eq!(answer("72F to C"), Some("22.222222 °C".to_string()));
+        assert_eq!(answer("1.5GB in MiB"), Some("1430.511475 MiB".to_string()));
+        // Different dimensions don't convert
+        assert_eq!(answer("10 km in kg"), None);
+    }
+
+    #[test]
+    fn bases() {
+        assert_eq!(answer("0x1F in dec"), Some("31".to_string()));
+        assert_eq!(answer("255 in hex"), Some("0xFF".to_string()));
+        assert_eq!(detail("0x1F in dec", "Binary"), Some("0b11111".to_string()));
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(answer("2 ^ 10"), Some("1024".to_string()));
+        assert_eq!(answer("42"), None);
+    }
+
+    #[test]
+    fn durations_from_now() {
+        assert_eq!(
+            detail("now + 3 days", "From now"),
+            Some("in 3 days".to_string())
+        );
+        assert_eq!(
+            detail("now - 2h", "From now"),
+            Some("2 hours ago".to_string())
+        );
+        assert_eq!(
+            detail("now + 1 week + 2 days", "From now"),
+            Some("in 9 days".to_string())
+        );
+    }
+
+    #[test]
+    fn durations_with_mixed_signs() {
+        assert_eq!(
+            detail("now - 1d + 2h", "From now"),
+            Some("22 hours ago".to_string())
+        );
+        assert_eq!(
+            detail("now + 1d - 2h", "From now"),
+            Some("in 22 hours".to_string())
+        );
+        assert_eq!(
+            detail("now + 1 week - 2 days", "From now"),
+            Some("in 5 days".to_string())
+        );
+        // The time between dates can't be added to
+        assert_eq!(answer("2024-12-25 - 2024-12-24 + 1d"), None);
+    }
+
+    #[test]
+    fn repeated_operators_are_not_answered() {
+        assert_eq!(answer("now + + 1d"), None);
+        assert_eq!(answer("now - - 1d"), None);
+        assert_eq!(answer("now + 1d +"), None);
+        assert_eq!(answer("now +"), None);
+    }
+
+    #[test]
+    fn unix_time() {
+        let local = Local.timestamp_opt(1_700_000_000, 0).unwrap();
+        assert_eq!(
+            answer("unix 1700000000"),
+            Some(local.format("%Y-%m-%d %H:%M").to_string())
+        );
+        assert_eq!(
+            detail("unix 1700000000", "Unix time"),
+            Some("1700000000".to_string())
+        );
+        assert_eq!(
+            answer("2024-01-01 in unix"),
+            Some(
+                Local
+                    .with_ymd_and_hms(2024, 1, 1, 0, 0, 0)
+                    .unwrap()
+                    .timestamp()
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn time_between_dates() {
+        assert_eq!(answer("2024-12-25 - 2024-12-24"), Some("1 day".to_string()));
+    }
+
+    #[test]
+    fn dates_out_of_range_are_not_answered() {
+        assert_eq!(answer("now + 100000000 days"), None);
+        assert_eq!(answer("now + 9999999999999 days"), None);
+        assert_eq!(answer("now - 9999999999999 weeks"), None);
+        assert_eq!(answer("today + 9223372036854775807 s"), None);
+        assert_eq!(answer("unix 99999999999999999"), None);
+    }
+}
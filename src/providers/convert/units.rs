@@ -0,0 +1,156 @@
+//! Units of measure, and converting between those of the same kind.
+use super::expr::number_prefix;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dimension {
+    Length,
+    Area,
+    Volume,
+    Mass,
+    Time,
+    Speed,
+    Data,
+    Temperature,
+}
+
+impl Dimension {
+    pub fn name(self) -> &'static str {
+        match self {
+            Dimension::Length => "length",
+            Dimension::Area => "area",
+            Dimension::Volume => "volume",
+            Dimension::Mass => "mass",
+            Dimension::Time => "time",
+            Dimension::Speed => "speed",
+            Dimension::Data => "data",
+            Dimension::Temperature => "temperature",
+        }
+    }
+}
+
+/// A unit is `(value + offset) * factor` of the base unit of its dimension
+pub struct Unit {
+    /// The first name is the one that's shown
+    pub names: &'static [&'static str],
+    pub dimension: Dimension,
+    factor: f64,
+    offset: f64,
+}
+
+impl Unit {
+    pub fn to_base(&self, value: f64) -> f64 {
+        (value + self.offset) * self.factor
+    }
+
+    pub fn to_unit(&self, value: f64) -> f64 {
+        value / self.factor - self.offset
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.names[0]
+    }
+}
+
+macro_rules! unit {
+    ($dimension:ident, $factor:expr, $($name:expr),+) => {
+        Unit { names: &[$($name),+], dimension: Dimension::$dimension, factor: $factor, offset: 0. }
+    };
+}
+
+const UNITS: &[Unit] = &[
+    unit!(Length, 1., "m", "meter", "meters", "metre", "metres"),
+    unit!(Length, 1e3, "km", "kilometer", "kilometers"),
+    unit!(Length, 1e-2, "cm", "centimeter", "centimeters"),
+    unit!(Length, 1e-3, "mm", "millimeter", "millimeters"),
+    unit!(Length, 1e-6, "µm", "um", "micrometer", "micrometers"),
+    unit!(Length, 1609.344, "mi", "mile", "miles"),
+    unit!(Length, 0.9144, "yd", "yard", "yards"),
+    unit!(Length, 0.3048, "ft", "foot", "feet"),
+    unit!(Length, 0.0254, "in", "inch", "inches", "\""),
+    unit!(Length, 1852., "nmi", "nautical mile", "nautical miles"),
+    unit!(Area, 1., "m²", "m2", "sqm"),
+    unit!(Area, 1e6, "km²", "km2"),
+    unit!(Area, 1e4, "ha", "hectare", "hectares"),
+    unit!(Area, 4046.8564224, "acre", "acres"),
+    unit!(Area, 0.09290304, "ft²", "ft2", "sqft"),
+    unit!(Volume, 1e-3, "l", "L", "liter", "liters", "litre", "litres"),
+    unit!(Volume, 1e-6, "ml", "mL", "milliliter", "milliliters"),
+    unit!(Volume, 1., "m³", "m3"),
+    unit!(Volume, 3.785411784e-3, "gal", "gallon", "gallons"),
+    unit!(Volume, 2.95735295625e-5, "fl oz", "floz"),
+    unit!(Volume, 2.365882365e-4, "cup", "cups"),
+    unit!(Mass, 1., "kg", "kilogram", "kilograms", "kilo", "kilos"),
+    unit!(Mass, 1e-3, "g", "gram", "grams"),
+    unit!(Mass, 1e-6, "mg", "milligram", "milligrams"),
+    unit!(Mass, 1e3, "t", "tonne", "tonnes", "ton", "tons"),
+    unit!(Mass, 0.45359237, "lb", "lbs", "pound", "pounds"),
+    unit!(Mass, 0.028349523125, "oz", "ounce", "ounces"),
+    unit!(Mass, 6.35029318, "st", "stone", "stones"),
+    unit!(Time, 1., "s", "sec", "second", "seconds"),
+    unit!(Time, 1e-3, "ms", "millisecond", "milliseconds"),
+    unit!(Time, 60., "min", "minute", "minutes"),
+    unit!(Time, 3600., "h", "hr", "hour", "hours"),
+    unit!(Time, 86400., "d", "day", "days"),
+    unit!(Time, 604800., "wk", "week", "weeks"),
+    unit!(Time, 31557600., "yr", "year", "years"),
+    unit!(Speed, 1., "m/s", "mps"),
+    unit!(Speed, 1. / 3.6, "km/h", "kmh", "kph"),
+    unit!(Speed, 0.44704, "mph"),
+    unit!(Speed, 1852. / 3600., "kn", "knot", "knots"),
+    unit!(Data, 1., "B", "byte", "bytes"),
+    unit!(Data, 0.125, "bit", "bits"),
+    unit!(Data, 1e3, "KB", "kB"),
+    unit!(Data, 1e6, "MB"),
+    unit!(Data, 1e9, "GB"),
+    unit!(Data, 1e12, "TB"),
+    unit!(Data, 1e15, "PB"),
+    unit!(Data, 1024., "KiB"),
+    unit!(Data, 1048576., "MiB"),
+    unit!(Data, 1073741824., "GiB"),
+    unit!(Data, 1099511627776., "TiB"),
+    unit!(Data, 125., "kbit", "kbps"),
+    unit!(Data, 125e3, "Mbit", "Mbps"),
+    unit!(Data, 125e6, "Gbit", "Gbps"),
+    Unit {
+        names: &["K", "kelvin"],
+        dimension: Dimension::Temperature,
+        factor: 1.,
+        offset: 0.,
+    },
+    Unit {
+        names: &["°C", "C", "celsius"],
+        dimension: Dimension::Temperature,
+        factor: 1.,
+        offset: 273.15,
+    },
+    Unit {
+        names: &["°F", "F", "fahrenheit"],
+        dimension: Dimension::Temperature,
+        factor: 5. / 9.,
+        offset: 459.67,
+    },
+];
+
+/// A unit by one of its names, the case only matters when it must
+pub fn find(name: &str) -> Option<&'static Unit> {
+    let name = name.trim();
+    UNITS
+        .iter()
+        .find(|unit| unit.names.contains(&name))
+        .or_else(|| {
+            UNITS
+                .iter()
+                .find(|unit| unit.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+        })
+}
+
+/// A number followed by a unit, like `10 km` or `72F`
+pub fn quantity(text: &str) -> Option<(f64, &'static Unit)> {
+    let text = text.trim();
+    let negative = text.starts_with('-');
+    let chars: Vec<char> = text.chars().skip(negative as usize).collect();
+    let (value, len) = number_prefix(&chars)?;
+    let unit: String = chars[len..].iter().collect();
+    let value = if negative { -value } else { value };
+    Some((value, find(&unit)?))
+}
@@ -0,0 +1,259 @@
+//! URLs and paths typed in the query, opened with the application that
+//! handles them.
+use ini::Ini;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use xdg::BaseDirectories;
+
+use crate::config::expand_home;
+use crate::launch::{exec_quote, ResultAction};
+use crate::preview::text_head;
+use crate::state::Provider;
+use crate::SearchResult;
+
+/// Files without a known extension are text if their first bytes are
+const TEXT_SNIFF_BYTES: u64 = 512;
+/// MIME types of common extensions, to find the default application
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("rs", "text/rust"),
+    ("py", "text/x-python"),
+    ("sh", "application/x-shellscript"),
+    ("toml", "application/toml"),
+    ("json", "application/json"),
+    ("xml", "application/xml"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("svg", "image/svg+xml"),
+    ("webp", "image/webp"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("flac", "audio/flac"),
+    ("wav", "audio/x-wav"),
+    ("mp4", "video/mp4"),
+    ("mkv", "video/x-matroska"),
+    ("webm", "video/webm"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+];
+
+pub struct Location {
+    /// Desktop file name of the default application of each MIME type
+    defaults: HashMap<String, String>,
+    /// Application names by desktop file name
+    applications: HashMap<String, String>,
+}
+
+/// The `mimeapps.list` files, most important first
+fn mimeapps_files() -> Vec<PathBuf> {
+    let dirs = match BaseDirectories::new() {
+        Ok(dirs) => dirs,
+        Err(_) => return vec![],
+    };
+    let mut files = vec![dirs.get_config_home().join("mimeapps.list")];
+    files.extend(
+        dirs.get_config_dirs()
+            .iter()
+            .map(|d| d.join("mimeapps.list")),
+    );
+    files.push(dirs.get_data_home().join("applications/mimeapps.list"));
+    files.extend(
+        dirs.get_data_dirs()
+            .iter()
+            .map(|d| d.join("applications/mimeapps.list")),
+    );
+    files
+}
+
+fn load_defaults() -> HashMap<String, String> {
+    let mut defaults = HashMap::new();
+    for file in mimeapps_files() {
+        let info = match Ini::load_from_file(&file) {
+            Ok(info) => info,
+            Err(_) => continue,
+        };
+        if let Some(section) = info.section(Some("Default Applications")) {
+            for (mime, applications) in section.iter() {
+                if let Some(first) = applications.split(';').find(|a| !a.is_empty()) {
+                    defaults
+                        .entry(mime.to_string())
+                        .or_insert_with(|| first.to_string());
+                }
+            }
+        }
+    }
+    defaults
+}
+
+/// The URL a query is, with `https://` when it's like `example.com/foo`
+fn url(query: &str) -> Option<String> {
+    if query.is_empty() || query.contains(char::is_whitespace) {
+        return None;
+    }
+    if let Some(i) = query.find("://") {
+        let scheme = &query[..i];
+        let valid = |c: char| c.is_ascii_alphanumeric() || "+.-".contains(c);
+        if !scheme.is_empty() && scheme.chars().all(valid) && query.len() > i + 3 {
+            return Some(query.to_string());
+        }
+        return None;
+    }
+    if query.starts_with("mailto:") {
+        return Some(query.to_string());
+    }
+    // Without a scheme, a domain followed by a path, or starting with www.
+    let host = query.split('/').next().unwrap_or("");
+    if host.len() == query.len() && !query.starts_with("www.") {
+        return None;
+    }
+    let domain = host.split(':').next().unwrap_or("");
+    let labels: Vec<&str> = domain.split('.').collect();
+    let label = |l: &&str| !l.is_empty() && l.chars().all(|c| c.is_alphanumeric() || c == '-');
+    let top_level = labels.last().unwrap_or(&"");
+    if labels.len() < 2
+        || !labels.iter().all(label)
+        || top_level.len() < 2
+        || !top_level.chars().all(char::is_alphabetic)
+    {
+        return None;
+    }
+    Some(format!("https://{}", query))
+}
+
+/// The existing file or directory a query is
+fn path(query: &str) -> Option<PathBuf> {
+    let starts = ["/", "~", "./", "../"];
+    if !starts.iter().any(|s| query.starts_with(s)) {
+        return None;
+    }
+    let path = expand_home(query);
+    if path.exists() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+fn mime_type(path: &Path) -> &'static str {
+    if path.is_dir() {
+        return "inode/directory";
+    }
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    let known = MIME_TYPES
+        .iter()
+        .find(|(e, _)| extension.as_deref() == Some(e));
+    if let Some((_, mime)) = known {
+        return mime;
+    }
+    match text_head(path, TEXT_SNIFF_BYTES) {
+        Some(_) => "text/plain",
+        None => "application/octet-stream",
+    }
+}
+
+/// Files that can be executed, and are programs or scripts: the executable
+/// bit alone is set on everything in some file systems
+fn is_executable(path: &Path) -> bool {
+    let executable = fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false);
+    let mut magic = [0; 4];
+    executable
+        && fs::File::open(path)
+            .and_then(|mut file| file.read_exact(&mut magic))
+            .is_ok()
+        && (magic.starts_with(b"#!") || magic == *b"\x7fELF")
+}
+
+fn result(name: String, description: String, command: String) -> SearchResult {
+    SearchResult::new(Provider::Location, name, description, command)
+}
+
+/// Running a program or a script that was typed, from the actions menu
+/// only: Enter opens it like any other file
+pub fn actions(result: &SearchResult) -> Vec<ResultAction> {
+    if is_executable(Path::new(&result.name)) {
+        vec![ResultAction::Run {
+            name: "Run".to_string(),
+            command: exec_quote(&result.name),
+        }]
+    } else {
+        vec![]
+    }
+}
+
+impl Location {
+    pub fn new() -> Self {
+        Self {
+            defaults: load_defaults(),
+            applications: HashMap::new(),
+        }
+    }
+
+    pub fn set_cache(&mut self, cache: &[SearchResult]) {
+        self.applications = cache
+            .iter()
+            .filter_map(|result| {
+                let path = Path::new(result.desktop_entry_path.as_ref()?);
+                let file = path.file_name()?.to_str()?;
+                Some((file.to_string(), result.name.clone()))
+            })
+            .collect();
+    }
+
+    /// What opens a MIME type by default, by name
+    fn handler(&self, mime: &str) -> Option<&String> {
+        self.defaults
+            .get(mime)
+            .and_then(|desktop_file| self.applications.get(desktop_file))
+    }
+
+    /// Open what the query points to with `xdg-open`. The name is the
+    /// full path or URL.
+    pub fn result(&self, query: &str) -> Option<SearchResult> {
+        let query = query.trim();
+        if let Some(path) = path(query) {
+            let name = path.display().to_string();
+            let mime = mime_type(&path);
+            let description = match self.handler(mime) {
+                Some(application) => format!("Open with {} · {}", application, mime),
+                None => format!("Open · {}", mime),
+            };
+            let command = format!("xdg-open {}", exec_quote(&name));
+            return Some(result(name, description, command));
+        }
+        let url = url(query)?;
+        let scheme = url.split(':').next().unwrap_or("");
+        let description = match self.handler(&format!("x-scheme-handler/{}", scheme)) {
+            Some(application) => format!("Open with {}", application),
+            None => "Open".to_string(),
+        };
+        let command = format!("xdg-open {}", exec_quote(&url));
+        Some(result(url, description, command))
+    }
+}
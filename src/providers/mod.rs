@@ -0,0 +1,217 @@
+//! Results made from the query itself, instead of searched for in the cache.
+//! They are made again for every query, and shown around the search results.
+use std::fs::DirBuilder;
+use std::io;
+use std::os::unix::fs::DirBuilderExt;
+use std::path::PathBuf;
+use xdg::BaseDirectories;
+
+use crate::config::{Config, ProvidersConfig};
+use crate::launch::ResultAction;
+use crate::state::Provider;
+use crate::SearchResult;
+
+pub mod aliases;
+use aliases::Aliases;
+
+pub mod characters;
+use characters::Characters;
+
+pub mod clipboard;
+use clipboard::Clipboard;
+
+pub mod convert;
+
+pub mod location;
+use location::Location;
+
+pub mod run;
+use run::Run;
+
+pub mod session;
+use session::Session;
+
+pub mod ssh;
+use ssh::Ssh;
+
+pub mod web;
+use web::Web;
+
+/// What the providers make of a query
+#[derive(Default)]
+pub struct Answers {
+    /// Shown before the search results
+    pub first: Vec<SearchResult>,
+    /// Shown after the search results
+    pub last: Vec<SearchResult>,
+    /// Shown instead of `first` when it and the search results are empty
+    pub fallback: Vec<SearchResult>,
+    /// The query is meant for a provider, and isn't searched for
+    pub exclusive: bool,
+}
+
+/// The search terms of a query for a provider that's asked for by
+/// keyword, like `ssh web`. The keyword alone has no terms.
+pub fn keyword_terms<'a>(query: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = query.trim_start().strip_prefix(keyword)?;
+    if rest.is_empty() {
+        Some("")
+    } else if rest.starts_with(' ') {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+/// A file in `$XDG_DATA_HOME/fuzzle`. The directory is created if it's
+/// missing, and then only the user can open it: the clipboard history
+/// and the commands run can hold secrets.
+pub fn data_path(name: &str) -> io::Result<PathBuf> {
+    let path = BaseDirectories::with_prefix("fuzzle")
+        .map_err(|_| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?
+        .get_data_file(name);
+    if let Some(dir) = path.parent() {
+        DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    }
+    Ok(path)
+}
+
+pub struct Providers {
+    config: ProvidersConfig,
+    aliases: Aliases,
+    characters: Characters,
+    clipboard: Clipboard,
+    location: Location,
+    run: Run,
+    session: Session,
+    ssh: Ssh,
+    web: Web,
+}
+
+impl Providers {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.providers.clone(),
+            aliases: Aliases::new(&config.aliases),
+            characters: Characters::new(),
+            clipboard: if config.providers.clipboard {
+                Clipboard::new()
+            } else {
+                Clipboard::default()
+            },
+            location: Location::new(),
+            run: Run::new(),
+            session: Session::new(&config.session),
+            ssh: Ssh::new(config),
+            web: Web::new(&config.web),
+        }
+    }
+
+    /// Let providers learn from the cache, like the executables to complete
+    pub fn set_cache(&mut self, cache: &[SearchResult]) {
+        self.aliases.set_cache(cache);
+        self.location.set_cache(cache);
+        self.run.set_cache(cache);
+        self.session.set_cache();
+    }
+
+    pub fn answer(&self, query: &str) -> Answers {
+        let mut answers = Answers::default();
+        if let (true, Some(terms)) = (
+            self.config.clipboard,
+            keyword_terms(query, clipboard::PREFIX),
+        ) {
+            answers.first = self.clipboard.results(terms);
+            answers.exclusive = true;
+            return answers;
+        }
+        if let (true, Some(terms)) = (self.config.ssh, keyword_terms(query, ssh::PREFIX)) {
+            answers.first = self.ssh.results(terms);
+            answers.exclusive = true;
+            return answers;
+        }
+        if self.config.characters {
+            if let Some(results) = self.characters.shortcode(query) {
+                answers.first = results;
+                answers.exclusive = true;
+                return answers;
+            }
+            answers.first.extend(self.characters.code_point(query));
+        }
+        if self.config.locations {
+            answers.first.extend(self.location.result(query));
+        }
+        if self.config.converter {
+            answers.first.extend(convert::result(query));
+        }
+        answers.first.extend(self.aliases.result(query));
+        if self.config.commands {
+            match run::command(query) {
+                Some(command) => {
+                    answers.first.extend(self.run.results(command));
+                    answers.exclusive = true;
+                    return answers;
+                }
+                None => answers.fallback = self.run.results(query.trim()),
+            }
+        }
+        if self.config.session {
+            answers.last.extend(self.session.results(query));
+        }
+        if self.config.characters {
+            answers.last.extend(self.characters.by_name(query));
+        }
+        if self.config.web {
+            answers.first.extend(self.web.shortcut(query));
+            answers.fallback.extend(self.web.fallback(query));
+        }
+        answers
+    }
+
+    /// The query, completed to a result
+    pub fn complete(&self, query: &str, result: &SearchResult) -> Option<String> {
+        match result.provider {
+            Provider::Command => Some(run::complete(query, result)),
+            _ => None,
+        }
+    }
+
+    /// Whether a result must be confirmed before it's launched
+    pub fn confirms(&self, result: &SearchResult) -> bool {
+        match result.provider {
+            Provider::Session => self.session.confirms(result),
+            _ => false,
+        }
+    }
+
+    /// Tell providers that one of their results was launched
+    pub fn launched(&mut self, result: &SearchResult) {
+        match result.provider {
+            Provider::Command => self.run.remember(&result.command),
+            Provider::Ssh => self.ssh.remember(&result.name),
+            _ => (),
+        }
+    }
+
+    /// Actions of the menu of a result that depend on the result itself
+    pub fn actions(&self, result: &SearchResult) -> Vec<ResultAction> {
+        match result.provider {
+            Provider::Location => location::actions(result),
+            _ => vec![],
+        }
+    }
+
+    /// Remove a result from the history it comes from
+    pub fn forget(&mut self, result: &SearchResult) {
+        if let Provider::Clipboard = result.provider {
+            self.clipboard.forget(&result.command);
+        }
+    }
+
+    /// Remove every result from the history a result comes from
+    pub fn clear_history(&mut self, result: &SearchResult) {
+        if let Provider::Clipboard = result.provider {
+            self.clipboard.clear();
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Running the query as a shell command, with a history of the commands
+//! that were run and completion of executable names.
+use std::fs::{self, File};
+use std::io::{self, Write};
+
+use super::data_path;
+use crate::launch::shell;
+use crate::state::Provider;
+use crate::SearchResult;
+
+/// Queries starting with one of these are commands
+const PREFIXES: &[char] = &['>', '!'];
+const HISTORY_FILE: &str = "history";
+/// Older commands are forgotten
+const HISTORY_SIZE: usize = 500;
+const MAX_HISTORY_RESULTS: usize = 20;
+const MAX_COMPLETIONS: usize = 20;
+
+pub struct Run {
+    /// Oldest first, without duplicates
+    history: Vec<String>,
+    /// Names of the executables in `$PATH`, sorted
+    executables: Vec<String>,
+    icon_path: Option<String>,
+}
+
+/// The command in a query that starts with a prefix
+pub fn command(query: &str) -> Option<&str> {
+    if query.starts_with(PREFIXES) {
+        Some(query[1..].trim())
+    } else {
+        None
+    }
+}
+
+/// The query with its command replaced by the one of a result
+pub fn complete(query: &str, result: &SearchResult) -> String {
+    match command(query) {
+        Some(_) => format!("{}{}", &query[..1], result.command),
+        None => result.command.clone(),
+    }
+}
+
+fn save_history(history: &[String]) -> io::Result<()> {
+    let path = data_path(HISTORY_FILE)?;
+    let mut file = File::create(path)?;
+    for command in history {
+        writeln!(file, "{}", command)?;
+    }
+    Ok(())
+}
+
+impl Run {
+    pub fn new() -> Self {
+        let history = data_path(HISTORY_FILE)
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| content.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        Self {
+            history,
+            executables: vec![],
+            icon_path: None,
+        }
+    }
+
+    pub fn set_cache(&mut self, cache: &[SearchResult]) {
+        let binaries = cache
+            .iter()
+            .filter(|result| result.provider == Provider::Binary);
+        self.executables = binaries.clone().map(|result| result.name.clone()).collect();
+        self.executables.sort();
+        self.executables.dedup();
+        // Binaries have a terminal icon, which suits commands too
+        self.icon_path = binaries
+            .filter_map(|result| result.icon_path.clone())
+            .next();
+    }
+
+    /// The command itself, the commands run before that contain it, most
+    /// recent first, and the executables it's the start of
+    pub fn results(&self, command: &str) -> Vec<SearchResult> {
+        if command.is_empty() {
+            return self
+                .history
+                .iter()
+                .rev()
+                .take(MAX_HISTORY_RESULTS)
+                .map(|line| self.result(line, "From history"))
+                .collect();
+        }
+        let mut results = vec![self.result(command, &format!("Run with {}", shell()))];
+        results.extend(
+            self.history
+                .iter()
+                .rev()
+                .filter(|line| *line != command && line.contains(command))
+                .take(MAX_HISTORY_RESULTS)
+                .map(|line| self.result(line, "From history")),
+        );
+        if !command.contains(char::is_whitespace) {
+            let start = match self
+                .executables
+                .binary_search_by(|e| e.as_str().cmp(command))
+            {
+                Ok(i) => i + 1,
+                Err(i) => i,
+            };
+            let completions: Vec<SearchResult> = self.executables[start..]
+                .iter()
+                .take_while(|name| name.starts_with(command))
+                // Already there if it was run before
+                .filter(|name| !results.iter().any(|result| result.command == **name))
+                .take(MAX_COMPLETIONS)
+                .map(|name| self.result(name, "Executable in $PATH"))
+                .collect();
+            results.extend(completions);
+        }
+        results
+    }
+
+    fn result(&self, command: &str, description: &str) -> SearchResult {
+        SearchResult {
+            icon_path: self.icon_path.clone(),
+            ..SearchResult::new(
+                Provider::Command,
+                command.to_string(),
+                description.to_string(),
+                command.to_string(),
+            )
+        }
+    }
+
+    /// Put a command at the end of the history, and save it
+    pub fn remember(&mut self, command: &str) {
+        self.history.retain(|line| line != command);
+        self.history.push(command.to_string());
+        let excess = self.history.len().saturating_sub(HISTORY_SIZE);
+        self.history.drain(..excess);
+        if let Err(e) = save_history(&self.history) {
+            eprintln!("fuzzle: can't save the command history: {}", e);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Locking the screen, logging out, and suspending, rebooting or turning
+//! off the computer. They are shown after the search results, so that
+//! they don't get in the way of applications.
+
+use crate::config::SessionConfig;
+use crate::dirutils::search_icon;
+use crate::state::Provider;
+use crate::SearchResult;
+
+/// Queries shorter than this don't look for session actions
+const MIN_QUERY_CHARS: usize = 2;
+
+/// Name, keywords to find it with besides its name, icon, and whether it
+/// ends the session or turns the computer off, and asks first
+const ACTIONS: &[(&str, &[&str], &str, bool)] = &[
+    (
+        "Lock screen",
+        &["lock", "away"],
+        "system-lock-screen",
+        false,
+    ),
+    (
+        "Log out",
+        &["logout", "sign", "exit", "session"],
+        "system-log-out",
+        true,
+    ),
+    ("Suspend", &["sleep", "standby"], "system-suspend", false),
+    ("Hibernate", &["sleep", "disk"], "system-hibernate", true),
+    ("Reboot", &["restart"], "system-reboot", true),
+    (
+        "Power off",
+        &["shutdown", "poweroff", "halt", "turn"],
+        "system-shutdown",
+        true,
+    ),
+];
+
+struct SessionAction {
+    name: &'static str,
+    keywords: &'static [&'static str],
+    icon: &'static str,
+    destructive: bool,
+    command: String,
+    icon_path: Option<String>,
+}
+
+pub struct Session {
+    actions: Vec<SessionAction>,
+    confirm: bool,
+    icons_resolved: bool,
+}
+
+/// Whether every word of the query starts a word of the name or a keyword
+fn matches(action: &SessionAction, words: &[String]) -> bool {
+    let name = action.name.to_lowercase();
+    let candidates: Vec<&str> = name
+        .split_whitespace()
+        .chain(action.keywords.iter().cloned())
+        .collect();
+    words
+        .iter()
+        .all(|word| candidates.iter().any(|c| c.starts_with(word.as_str())))
+}
+
+impl Session {
+    pub fn new(config: &SessionConfig) -> Self {
+        let commands = [
+            &config.lock,
+            &config.logout,
+            &config.suspend,
+            &config.hibernate,
+            &config.reboot,
+            &config.power_off,
+        ];
+        let actions = ACTIONS
+            .iter()
+            .zip(commands.iter())
+            // An empty command turns the action off
+            .filter(|(_, command)| !command.trim().is_empty())
+            .map(
+                |(&(name, keywords, icon, destructive), command)| SessionAction {
+                    name,
+                    keywords,
+                    icon,
+                    destructive,
+                    command: command.to_string(),
+                    icon_path: None,
+                },
+            )
+            .collect();
+        Self {
+            actions,
+            confirm: config.confirm,
+            icons_resolved: false,
+        }
+    }
+
+    /// Look for the icons in the icon theme, once the cache is there
+    pub fn set_cache(&mut self) {
+        if self.icons_resolved {
+            return;
+        }
+        for action in &mut self.actions {
+            action.icon_path = search_icon(action.icon);
+        }
+        self.icons_resolved = true;
+    }
+
+    /// The actions the query is the start of, by name or keyword
+    pub fn results(&self, query: &str) -> Vec<SearchResult> {
+        if query.trim().chars().count() < MIN_QUERY_CHARS {
+            return vec![];
+        }
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        self.actions
+            .iter()
+            .filter(|action| matches(action, &words))
+            .map(|action| SearchResult {
+                icon_path: action.icon_path.clone(),
+                keywords: action.keywords.join(";"),
+                ..SearchResult::new(
+                    Provider::Session,
+                    action.name.to_string(),
+                    action.command.clone(),
+                    action.command.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Whether launching a result must be confirmed first
+    pub fn confirms(&self, result: &SearchResult) -> bool {
+        self.confirm
+            && self
+                .actions
+                .iter()
+                .any(|action| action.destructive && action.name == result.name)
+    }
+}
@@ -0,0 +1,437 @@
+//! SSH hosts from `~/.ssh/config` and `~/.ssh/known_hosts`, searched with
+//! `ssh <terms>` and opened in a terminal. Hosts connected to often and
+//! lately come first.
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::data_path;
+use crate::config::{expand_home, Config};
+use crate::launch::exec_quote;
+use crate::search::{score, CaseMatching, MatchMode};
+use crate::state::Provider;
+use crate::SearchResult;
+
+/// Queries for hosts start with this, see `keyword_terms`
+pub const PREFIX: &str = "ssh";
+const FRECENCY_FILE: &str = "ssh";
+/// `Include` directives nested deeper than this are ignored, like ssh does
+const MAX_INCLUDE_DEPTH: usize = 16;
+const DAY: u64 = 24 * 60 * 60;
+
+/// A host to connect to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Host {
+    /// What's shown and typed, like `web1` or `example.org:2222`
+    pub name: String,
+    /// What ssh is given, like `web1` or `ssh://example.org:2222`
+    pub destination: String,
+    /// Where the name comes from, and where it leads
+    pub description: String,
+}
+
+impl Host {
+    /// The result that connects to the host. It's run in a terminal, see
+    /// `Provider::in_terminal`.
+    pub fn result(&self) -> SearchResult {
+        SearchResult::new(
+            Provider::Ssh,
+            self.name.clone(),
+            self.description.clone(),
+            format!("ssh {}", exec_quote(&self.destination)),
+        )
+    }
+}
+
+/// How often and how lately each host was connected to
+#[derive(Default)]
+struct Visits {
+    /// Host name to the number of connections and the last one, in unix time
+    hosts: HashMap<String, (u64, u64)>,
+}
+
+pub struct Ssh {
+    hosts: Vec<Host>,
+    visits: Visits,
+}
+
+/// Whether `text` matches `pattern`, where `*` is any text and `?` any char
+fn wildcard(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|i| wildcard(rest, &text[i..])),
+        Some((c, rest)) => match text.split_first() {
+            Some((t, text)) if *c == '?' || c == t => wildcard(rest, text),
+            _ => false,
+        },
+    }
+}
+
+fn is_pattern(name: &str) -> bool {
+    name.contains(['*', '?', '!'])
+}
+
+/// The files an `Include` argument names. Relative paths are in `ssh_dir`,
+/// and wildcards are only expanded in the file name.
+fn include_paths(argument: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let path = expand_home(argument);
+    let path = if path.is_absolute() {
+        path
+    } else {
+        ssh_dir.join(path)
+    };
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) if is_pattern(name) => name.chars().collect::<Vec<char>>(),
+        _ => return vec![path],
+    };
+    let dir = path.parent().unwrap_or(ssh_dir);
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                    wildcard(&name, &file_name.chars().collect::<Vec<char>>())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+/// Split a config line into its keyword and arguments. The keyword can be
+/// followed by spaces or by `=`, and arguments can be quoted.
+fn config_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let keyword = line[..end].to_lowercase();
+    let rest = line[end..].trim_start();
+    let rest = match rest.strip_prefix('=') {
+        Some(rest) => rest.trim_start(),
+        None => rest,
+    };
+    let mut arguments = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    arguments.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        arguments.push(current);
+    }
+    Some((keyword, arguments))
+}
+
+/// The hosts a `Host` block names first, by their place in the list, and
+/// where they lead
+#[derive(Default)]
+struct Block {
+    hosts: Vec<usize>,
+    hostname: Option<String>,
+    user: Option<String>,
+}
+
+/// A `HostName` with `%h` replaced by the host name and `%%` by `%`, the
+/// only tokens ssh expands there
+fn expand_hostname(hostname: &str, name: &str) -> String {
+    let mut expanded = String::new();
+    let mut chars = hostname.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('%', Some('h')) => expanded.push_str(name),
+            ('%', Some('%')) => expanded.push('%'),
+            (c, _) => {
+                expanded.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+    expanded
+}
+
+impl Block {
+    /// A block for the names of a `Host` line, listing those that aren't
+    /// listed yet where the line is
+    fn new(names: Vec<String>, hosts: &mut Vec<Host>) -> Self {
+        let mut block = Block::default();
+        for name in names {
+            if is_pattern(&name) || hosts.iter().any(|host| host.name == name) {
+                continue;
+            }
+            block.hosts.push(hosts.len());
+            hosts.push(Host {
+                destination: name.clone(),
+                description: String::new(),
+                name,
+            });
+        }
+        block
+    }
+
+    /// Describe the hosts of the block, once all its options are read
+    fn finish(self, hosts: &mut [Host]) {
+        for i in self.hosts {
+            let host = &mut hosts[i];
+            let hostname = self
+                .hostname
+                .as_ref()
+                .map(|hostname| expand_hostname(hostname, &host.name));
+            let target = match (&self.user, hostname) {
+                (Some(user), Some(hostname)) => format!(" · {}@{}", user, hostname),
+                (Some(user), None) => format!(" · {}@", user),
+                (None, Some(hostname)) => format!(" · {}", hostname),
+                (None, None) => String::new(),
+            };
+            host.description = format!("SSH config{}", target);
+        }
+    }
+}
+
+/// Collect the hosts of a config file and of the files it includes. Until
+/// its first `Host` or `Match` line, the options of a file go to `outer`,
+/// the block it's included in.
+fn read_config(
+    path: &Path,
+    ssh_dir: &Path,
+    depth: usize,
+    hosts: &mut Vec<Host>,
+    outer: &mut Block,
+) {
+    if depth > MAX_INCLUDE_DEPTH {
+        return;
+    }
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+    // The first value of an option is the one that counts, like in ssh
+    let mut own: Option<Block> = None;
+    for line in content.lines() {
+        let (keyword, arguments) = match config_line(line) {
+            Some(line) => line,
+            None => continue,
+        };
+        match keyword.as_str() {
+            "host" | "match" => {
+                if let Some(block) = own.take() {
+                    block.finish(hosts);
+                }
+                own = Some(if keyword == "host" {
+                    Block::new(arguments, hosts)
+                } else {
+                    Block::default()
+                });
+            }
+            _ => {
+                let block = own.as_mut().unwrap_or(&mut *outer);
+                match keyword.as_str() {
+                    "hostname" if block.hostname.is_none() => {
+                        block.hostname = arguments.into_iter().next()
+                    }
+                    "user" if block.user.is_none() => block.user = arguments.into_iter().next(),
+                    // What follows the include is still in the block
+                    "include" => {
+                        for argument in arguments {
+                            for path in include_paths(&argument, ssh_dir) {
+                                read_config(&path, ssh_dir, depth + 1, hosts, block);
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    if let Some(block) = own {
+        block.finish(hosts);
+    }
+}
+
+/// The hosts named in an ssh config file and the files it includes,
+/// without patterns. Relative includes are looked for in `ssh_dir`.
+pub fn config_hosts(path: &Path, ssh_dir: &Path) -> Vec<Host> {
+    let mut hosts = vec![];
+    read_config(path, ssh_dir, 0, &mut hosts, &mut Block::default());
+    hosts
+}
+
+/// The hosts of a `known_hosts` file. Hashed names can't be read back,
+/// and patterns, revoked keys and certificate authorities aren't hosts.
+pub fn known_hosts(content: &str) -> Vec<Host> {
+    let mut hosts = vec![];
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+            continue;
+        }
+        let names = line.split_whitespace().next().unwrap_or("");
+        for name in names.split(',') {
+            if name.starts_with('|') || is_pattern(name) || name.is_empty() {
+                continue;
+            }
+            // `[host]:port` for hosts on another port than 22
+            let host = match (name.starts_with('['), name.rfind("]:")) {
+                (true, Some(end)) => Host {
+                    name: format!("{}:{}", &name[1..end], &name[end + 2..]),
+                    destination: format!("ssh://{}:{}", &name[1..end], &name[end + 2..]),
+                    description: "Known host".to_string(),
+                },
+                _ => Host {
+                    name: name.to_string(),
+                    destination: name.to_string(),
+                    description: "Known host".to_string(),
+                },
+            };
+            if !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        }
+    }
+    hosts
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+impl Visits {
+    /// Lines of `<host> <connections> <last connection>`
+    fn parse(content: &str) -> Self {
+        let hosts = content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let host = fields.next()?.to_string();
+                let count = fields.next()?.parse().ok()?;
+                let last = fields.next()?.parse().ok()?;
+                Some((host, (count, last)))
+            })
+            .collect();
+        Self { hosts }
+    }
+
+    fn load() -> Self {
+        data_path(FRECENCY_FILE)
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let path = data_path(FRECENCY_FILE)?;
+        let mut file = File::create(path)?;
+        for (host, (count, last)) in &self.hosts {
+            writeln!(file, "{} {} {}", host, count, last)?;
+        }
+        Ok(())
+    }
+
+    /// Connections, weighted by how recent the last one is
+    fn frecency(&self, host: &str, now: u64) -> u64 {
+        let (count, last) = match self.hosts.get(host) {
+            Some(visits) => *visits,
+            None => return 0,
+        };
+        let age = now.saturating_sub(last);
+        let weight = if age < 4 * DAY {
+            100
+        } else if age < 14 * DAY {
+            70
+        } else if age < 31 * DAY {
+            50
+        } else if age < 90 * DAY {
+            30
+        } else {
+            10
+        };
+        count * weight
+    }
+}
+
+impl Ssh {
+    pub fn new(config: &Config) -> Self {
+        let ssh_dir = expand_home("~/.ssh");
+        let mut hosts = config_hosts(&ssh_dir.join("config"), &ssh_dir);
+        if config.ssh.known_hosts {
+            let content = fs::read_to_string(ssh_dir.join("known_hosts")).unwrap_or_default();
+            for host in known_hosts(&content) {
+                if !hosts.iter().any(|known| known.name == host.name) {
+                    hosts.push(host);
+                }
+            }
+        }
+        Self {
+            hosts,
+            visits: Visits::load(),
+        }
+    }
+
+    /// The hosts that match the terms, best match first, and those
+    /// connected to often and lately first among equally good matches.
+    /// A host that isn't known can be typed too.
+    pub fn results(&self, terms: &str) -> Vec<SearchResult> {
+        let now = now();
+        let search = |text: &str| score(text, terms, MatchMode::Fuzzy, false, CaseMatching::Smart);
+        let mut found: Vec<((i64, u64), SearchResult)> = self
+            .hosts
+            .iter()
+            .filter_map(|host| {
+                let (score, indices, description_indices) =
+                    match (search(&host.name), search(&host.description)) {
+                        (Some((score, indices)), _) => (score, indices, vec![]),
+                        (None, Some((score, indices))) => (score, vec![], indices),
+                        (None, None) => return None,
+                    };
+                let frecency = self.visits.frecency(&host.name, now);
+                let result = SearchResult {
+                    indices: Arc::new(indices),
+                    description_indices: Arc::new(description_indices),
+                    ..host.result()
+                };
+                Some(((score, frecency), result))
+            })
+            .collect();
+        found.sort_by_key(|(rank, _)| std::cmp::Reverse(*rank));
+        let mut results: Vec<SearchResult> = found.into_iter().map(|(_, result)| result).collect();
+        let typed = !terms.is_empty() && !terms.contains(char::is_whitespace);
+        if typed && !self.hosts.iter().any(|host| host.name == terms) {
+            let host = Host {
+                name: terms.to_string(),
+                destination: terms.to_string(),
+                description: "Connect with ssh".to_string(),
+            };
+            results.push(host.result());
+        }
+        results
+    }
+
+    /// Count a connection to a host, and save the counts
+    pub fn remember(&mut self, host: &str) {
+        let visits = self.visits.hosts.entry(host.to_string()).or_insert((0, 0));
+        visits.0 += 1;
+        visits.1 = now();
+        if let Err(e) = self.visits.save() {
+            eprintln!("fuzzle: can't save the ssh connections: {}", e);
+        }
+    }
+}
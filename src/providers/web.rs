@@ -0,0 +1,68 @@
+//! Search engine shortcuts, that open the results page in the browser.
+use std::collections::HashMap;
+
+use crate::config::{SearchEngine, WebConfig};
+use crate::launch::exec_quote;
+use crate::state::Provider;
+use crate::SearchResult;
+
+pub struct Web {
+    engines: HashMap<String, SearchEngine>,
+    fallback: Option<SearchEngine>,
+}
+
+/// Percent-encode search terms for a query string
+fn encode(terms: &str) -> String {
+    let mut encoded = String::new();
+    for byte in terms.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn result(engine: &SearchEngine, terms: &str) -> SearchResult {
+    let url = engine.url.replace("{}", &encode(terms));
+    let command = format!("xdg-open {}", exec_quote(&url));
+    SearchResult::new(
+        Provider::Web,
+        format!("Search {} for {}", engine.name, terms),
+        url,
+        command,
+    )
+}
+
+impl Web {
+    pub fn new(config: &WebConfig) -> Self {
+        Self {
+            engines: config.engines.clone(),
+            fallback: config.engines.get(&config.fallback).cloned(),
+        }
+    }
+
+    /// A search for a query like `<keyword> <terms>`
+    pub fn shortcut(&self, query: &str) -> Option<SearchResult> {
+        let query = query.trim_start();
+        let space = query.find(' ')?;
+        let terms = query[space..].trim();
+        if terms.is_empty() {
+            return None;
+        }
+        let engine = self.engines.get(&query[..space])?;
+        Some(result(engine, terms))
+    }
+
+    /// A search for the whole query, with the fallback engine
+    pub fn fallback(&self, query: &str) -> Option<SearchResult> {
+        let terms = query.trim();
+        match &self.fallback {
+            Some(engine) if !terms.is_empty() => Some(result(engine, terms)),
+            _ => None,
+        }
+    }
+}
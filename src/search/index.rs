@@ -0,0 +1,302 @@
+//! The cache, prepared for searching.
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::path::Path;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use super::normalize::{normalize, original_indices, CaseMatching};
+use super::pattern::{MatchMode, Matchers, Pattern};
+use crate::SearchResult;
+
+/// How many entries each task of the thread pool scores. The search
+/// stops between chunks when a newer query comes in.
+const CHUNK_SIZE: usize = 1024;
+
+/// An entry of the index that matched the query
+#[derive(Clone, Debug)]
+pub struct Match {
+    /// Position of the entry in `SearchIndex::entries`
+    pub entry: usize,
+    pub score: i64,
+    /// Char indices of the matched chars in the name, followed by the
+    /// desktop file name and keywords, see `haystack`
+    pub indices: Arc<Vec<usize>>,
+}
+
+/// Cache entries, plus the text we match each of them against,
+/// built once when the cache is loaded instead of at every keystroke.
+pub struct SearchIndex {
+    pub entries: Arc<Vec<SearchResult>>,
+    /// Normalized, so accents don't matter
+    haystacks: Vec<String>,
+    /// Where the chars of a normalized haystack come from, see `normalize`
+    origins: Vec<Option<Vec<usize>>>,
+}
+
+impl SearchIndex {
+    pub fn new(entries: Arc<Vec<SearchResult>>) -> Self {
+        let (haystacks, origins) = entries
+            .iter()
+            .map(|entry| normalize(&haystack(entry)))
+            .unzip();
+        Self {
+            entries,
+            haystacks,
+            origins,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Which part of an entry a match is in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchedField {
+    Name,
+    FileName,
+    Keywords,
+}
+
+impl MatchedField {
+    pub fn name(self) -> &'static str {
+        match self {
+            MatchedField::Name => "name",
+            MatchedField::FileName => "file name",
+            MatchedField::Keywords => "keywords",
+        }
+    }
+
+    /// The field of the first matched char
+    pub fn of(entry: &SearchResult, indices: &[usize]) -> Option<Self> {
+        let first = *indices.first()?;
+        let name_end = entry.name.chars().count();
+        let file_name_end = name_end + file_stem(entry).map_or(0, |stem| 1 + stem.chars().count());
+        if first < name_end {
+            Some(MatchedField::Name)
+        } else if first < file_name_end {
+            Some(MatchedField::FileName)
+        } else {
+            Some(MatchedField::Keywords)
+        }
+    }
+}
+
+fn file_stem(entry: &SearchResult) -> Option<&str> {
+    entry
+        .desktop_entry_path
+        .as_ref()
+        .and_then(|path| Path::new(path).file_stem())
+        .and_then(|stem| stem.to_str())
+}
+
+/// The text an entry is matched against, separated by spaces: its name,
+/// the desktop file name, which is often what people remember ("code"
+/// for "Visual Studio Code"), and the keywords.
+fn haystack(entry: &SearchResult) -> String {
+    let mut search_name = String::from(&entry.name);
+    if let Some(file_name) = file_stem(entry) {
+        search_name = search_name + " " + file_name;
+    };
+    for keyword in entry.keywords.split(';').filter(|k| !k.is_empty()) {
+        search_name = search_name + " " + keyword;
+    }
+    search_name
+}
+
+/// Searches an index, reusing the previous matches when the query is
+/// extended: if "fi" didn't match an entry, "fir" won't either.
+pub struct Searcher {
+    matchers: Matchers,
+    index: Arc<SearchIndex>,
+    /// How many of the best matches to return
+    limit: usize,
+    mode: MatchMode,
+    /// Parse the query with the skim-style extended syntax
+    extended: bool,
+    case: CaseMatching,
+    /// The last normalized query, and the entries that matched it
+    previous: Option<(String, Vec<usize>)>,
+}
+
+impl Searcher {
+    /// A fuzzy, smart case searcher, with the extended syntax
+    pub fn new(index: Arc<SearchIndex>, limit: usize) -> Self {
+        let case = CaseMatching::default();
+        Self {
+            matchers: Matchers::new(case),
+            index,
+            limit,
+            mode: MatchMode::default(),
+            extended: true,
+            case,
+            previous: None,
+        }
+    }
+
+    pub fn index(&self) -> &Arc<SearchIndex> {
+        &self.index
+    }
+
+    pub fn set_index(&mut self, index: Arc<SearchIndex>) {
+        self.index = index;
+        self.previous = None;
+    }
+
+    pub fn set_mode(&mut self, mode: MatchMode, extended: bool) {
+        self.mode = mode;
+        self.extended = extended;
+        self.previous = None;
+    }
+
+    pub fn set_case(&mut self, case: CaseMatching) {
+        self.matchers = Matchers::new(case);
+        self.case = case;
+        self.previous = None;
+    }
+
+    /// Score the candidates against the query on all cores, and return
+    /// the best `limit` of them, sorted by descending score.
+    /// Returns None if `cancelled` says so before we're done.
+    pub fn search(
+        &mut self,
+        query: &str,
+        cancelled: impl Fn() -> bool + Sync,
+    ) -> Option<Vec<Match>> {
+        let index = &self.index;
+        let matchers = &self.matchers;
+        let limit = self.limit;
+        let mode = self.mode;
+        let (query, _) = normalize(query);
+        let pattern = Pattern::parse(&query, mode, self.extended, self.case);
+        let all: Vec<usize>;
+        let candidates = match &self.previous {
+            Some((previous, candidates)) if Pattern::narrows(previous, &query, mode) => candidates,
+            _ => {
+                all = (0..index.len()).collect();
+                &all
+            }
+        };
+
+        // Each chunk keeps its own best matches, along with everything
+        // that matched, which is the candidate set for the next query
+        let chunks = candidates
+            .par_chunks(CHUNK_SIZE)
+            .map(|chunk| {
+                if cancelled() {
+                    return None;
+                }
+                let mut matched = Vec::new();
+                let mut best = TopK::new(limit);
+                for &entry in chunk {
+                    if let Some((score, indices)) =
+                        pattern.matches(matchers, &index.haystacks[entry])
+                    {
+                        // Always put desktop entry files first
+                        let bonus = if index.entries[entry].desktop_entry_path.is_some() {
+                            1000
+                        } else {
+                            0
+                        };
+                        matched.push(entry);
+                        best.push(Match {
+                            entry,
+                            score: score + bonus,
+                            indices: Arc::new(original_indices(
+                                indices,
+                                index.origins[entry].as_deref(),
+                            )),
+                        });
+                    }
+                }
+                Some((matched, best))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        // Chunks come back in order, so the candidates stay in index order
+        let mut matched = Vec::new();
+        let mut best = TopK::new(limit);
+        for (chunk_matched, chunk_best) in chunks {
+            matched.extend(chunk_matched);
+            best.extend(chunk_best);
+        }
+        self.previous = Some((query, matched));
+        Some(best.into_sorted_vec())
+    }
+}
+
+/// Orders matches from worst to best: by score, then earlier entries first
+struct Ranked(Match);
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .score
+            .cmp(&other.0.score)
+            .then_with(|| other.0.entry.cmp(&self.0.entry))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+/// Keeps the best `k` matches pushed into it, without sorting the rest
+struct TopK {
+    k: usize,
+    /// A min-heap, so the worst of the best is the one to drop
+    heap: BinaryHeap<Reverse<Ranked>>,
+}
+
+impl TopK {
+    fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    fn push(&mut self, m: Match) {
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(Ranked(m)));
+        } else if let Some(mut worst) = self.heap.peek_mut() {
+            let m = Ranked(m);
+            if m > worst.0 {
+                *worst = Reverse(m);
+            }
+        }
+    }
+
+    fn extend(&mut self, other: TopK) {
+        for Reverse(Ranked(m)) in other.heap {
+            self.push(m);
+        }
+    }
+
+    /// The matches, best first
+    fn into_sorted_vec(self) -> Vec<Match> {
+        // Sorting `Reverse`s in ascending order puts the best first
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(Ranked(m))| m)
+            .collect()
+    }
+}
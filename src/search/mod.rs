@@ -0,0 +1,170 @@
+//! Fuzzy search over the cache, run in a background thread so typing
+//! never waits for it.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use druid::{ExtEventSink, Selector};
+
+use crate::config::BehaviorConfig;
+use crate::SearchResult;
+
+mod index;
+pub use index::{Match, MatchedField, SearchIndex, Searcher};
+mod normalize;
+pub use normalize::CaseMatching;
+mod pattern;
+use normalize::{normalize, original_indices};
+pub use pattern::MatchMode;
+use pattern::{Matchers, Pattern};
+
+/// Sent with a `SearchResponse` when a search is done
+pub const SEARCH_FINISHED: Selector = Selector::new("fuzzle.search-finished");
+
+pub struct SearchResponse {
+    pub generation: usize,
+    /// Matches, sorted, pointing into `entries`
+    pub matches: Arc<Vec<Match>>,
+    pub entries: Arc<Vec<SearchResult>>,
+}
+
+enum Request {
+    Cache(Arc<Vec<SearchResult>>),
+    Query(String, usize),
+    Mode(MatchMode, bool),
+}
+
+/// Handle to the search thread.
+///
+/// Every query gets a generation number. The thread only answers the
+/// latest one, and stops searching as soon as a newer query comes in.
+pub struct SearchWorker {
+    sender: Sender<Request>,
+    generation: Arc<AtomicUsize>,
+}
+
+impl SearchWorker {
+    /// Start the search thread. Queries are only searched once no newer
+    /// query came in for the debounce delay, so fast typing doesn't queue up work.
+    pub fn spawn(sink: ExtEventSink, behavior: &BehaviorConfig) -> Self {
+        let (sender, receiver) = channel();
+        let generation = Arc::new(AtomicUsize::new(0));
+        let thread_generation = generation.clone();
+        let debounce = Duration::from_millis(behavior.search_debounce_ms);
+        let (max_results, case) = (behavior.max_results, behavior.case_matching);
+        thread::spawn(move || {
+            run(
+                receiver,
+                thread_generation,
+                sink,
+                debounce,
+                max_results,
+                case,
+            )
+        });
+        let worker = Self { sender, generation };
+        worker.set_mode(behavior.match_mode, behavior.extended_syntax);
+        worker
+    }
+
+    /// Replace the entries to search in
+    pub fn set_cache(&self, cache: Arc<Vec<SearchResult>>) {
+        let _ = self.sender.send(Request::Cache(cache));
+    }
+
+    /// Match the next queries with another mode
+    pub fn set_mode(&self, mode: MatchMode, extended: bool) {
+        let _ = self.sender.send(Request::Mode(mode, extended));
+    }
+
+    /// Start searching for a query, return its generation
+    pub fn search(&self, query: &str) -> usize {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let _ = self
+            .sender
+            .send(Request::Query(query.to_string(), generation));
+        generation
+    }
+
+    /// Generation of the last query, results from older ones are stale
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::SeqCst)
+    }
+}
+
+fn run(
+    receiver: Receiver<Request>,
+    generation: Arc<AtomicUsize>,
+    sink: ExtEventSink,
+    debounce: Duration,
+    max_results: usize,
+    case: CaseMatching,
+) {
+    let empty = Arc::new(SearchIndex::new(Arc::new(vec![])));
+    let mut searcher = Searcher::new(empty, max_results);
+    searcher.set_case(case);
+    let mut latest: Option<(String, usize)> = None;
+    while let Ok(request) = receiver.recv() {
+        // Skip to the latest query, older ones are already out of date.
+        // A new cache or mode means searching the latest query again.
+        let mut next = Some(request);
+        while let Some(request) = next {
+            match request {
+                Request::Cache(cache) => searcher.set_index(Arc::new(SearchIndex::new(cache))),
+                Request::Mode(mode, extended) => searcher.set_mode(mode, extended),
+                Request::Query(text, gen) => latest = Some((text, gen)),
+            }
+            next = receiver.recv_timeout(debounce).ok();
+        }
+        let (text, gen) = match &latest {
+            Some((text, gen)) => (text, *gen),
+            None => continue,
+        };
+        let cancelled = || generation.load(Ordering::SeqCst) != gen;
+        if let Some(matches) = searcher.search(text, cancelled) {
+            let response = SearchResponse {
+                generation: gen,
+                matches: Arc::new(matches),
+                entries: searcher.index().entries.clone(),
+            };
+            if sink
+                .submit_command(SEARCH_FINISHED, response, None)
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+/// The score of `text` for the query, and the char indices that match,
+/// the way a search would match them. None if it doesn't match.
+pub fn score(
+    text: &str,
+    query: &str,
+    mode: MatchMode,
+    extended: bool,
+    case: CaseMatching,
+) -> Option<(i64, Vec<usize>)> {
+    let (query, _) = normalize(query);
+    let (text, origins) = normalize(text);
+    Pattern::parse(&query, mode, extended, case)
+        .matches(&Matchers::new(case), &text)
+        .map(|(score, indices)| (score, original_indices(indices, origins.as_deref())))
+}
+
+/// Char indices of `text` that match the query, see `score`. Empty if it
+/// doesn't match.
+pub fn highlight(
+    text: &str,
+    query: &str,
+    mode: MatchMode,
+    extended: bool,
+    case: CaseMatching,
+) -> Vec<usize> {
+    score(text, query, mode, extended, case)
+        .map(|(_, indices)| indices)
+        .unwrap_or_default()
+}
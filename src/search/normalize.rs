@@ -0,0 +1,79 @@
+//! Folding text so that "cafe" finds "Café".
+use serde::Deserialize;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Whether upper and lower case letters are the same when matching
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaseMatching {
+    /// Ignore case, unless the query has upper case letters
+    Smart,
+    Ignore,
+    Respect,
+}
+
+impl CaseMatching {
+    /// Whether a term of the query should be matched with case
+    pub fn is_sensitive(self, term: &str) -> bool {
+        match self {
+            CaseMatching::Smart => term.chars().any(char::is_uppercase),
+            CaseMatching::Ignore => false,
+            CaseMatching::Respect => true,
+        }
+    }
+}
+
+impl Default for CaseMatching {
+    fn default() -> Self {
+        CaseMatching::Smart
+    }
+}
+
+/// Decompose the text (NFKD) and drop the combining marks, which takes
+/// accents off letters and turns ligatures and full width forms into
+/// plain letters.
+///
+/// Since that can change the number of chars, also returns for each char
+/// of the result the index of the char of `text` it came from. That's None
+/// when nothing changed, which is the case for most names.
+pub fn normalize(text: &str) -> (String, Option<Vec<usize>>) {
+    let mut normalized = String::with_capacity(text.len());
+    let mut origins = Vec::new();
+    let mut changed = false;
+    for (i, c) in text.chars().enumerate() {
+        let mut decomposed = std::iter::once(c).nfkd().filter(|d| !is_combining_mark(*d));
+        match (decomposed.next(), decomposed.next()) {
+            (Some(d), None) if d == c => {
+                normalized.push(c);
+                origins.push(i);
+            }
+            (first, second) => {
+                changed = true;
+                for d in first.into_iter().chain(second).chain(decomposed) {
+                    normalized.push(d);
+                    origins.push(i);
+                }
+            }
+        }
+    }
+    if changed {
+        (normalized, Some(origins))
+    } else {
+        (normalized, None)
+    }
+}
+
+/// Turn char indices into a normalized text back into indices into the
+/// original one
+pub fn original_indices(indices: Vec<usize>, origins: Option<&[usize]>) -> Vec<usize> {
+    match origins {
+        None => indices,
+        Some(origins) => {
+            let mut original: Vec<usize> = indices.iter().map(|&i| origins[i]).collect();
+            // A ligature matched by two letters is still one char
+            original.dedup();
+            original
+        }
+    }
+}
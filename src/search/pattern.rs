@@ -0,0 +1,606 @@
+//! Matching modes, and the skim-style extended query syntax.
+use druid::Data;
+use fuzzy_matcher::clangd::ClangdMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+use super::normalize::CaseMatching;
+
+/// How a query is matched against the entries
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MatchMode {
+    /// Characters in order, anywhere, scored like skim does
+    #[default]
+    Fuzzy,
+    /// Characters in order, anywhere, scored like clangd does
+    FuzzyClangd,
+    /// The name starts with the query
+    Prefix,
+    /// The query is anywhere in the name
+    Substring,
+    /// The query is the first letters of consecutive words: "vsc" finds "Visual Studio Code"
+    Initials,
+    /// The query is a whole word of the name
+    Exact,
+    /// The query is a regular expression
+    Regex,
+}
+
+impl MatchMode {
+    const ALL: [MatchMode; 7] = [
+        MatchMode::Fuzzy,
+        MatchMode::FuzzyClangd,
+        MatchMode::Prefix,
+        MatchMode::Substring,
+        MatchMode::Initials,
+        MatchMode::Exact,
+        MatchMode::Regex,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MatchMode::Fuzzy => "fuzzy",
+            MatchMode::FuzzyClangd => "fuzzy-clangd",
+            MatchMode::Prefix => "prefix",
+            MatchMode::Substring => "substring",
+            MatchMode::Initials => "initials",
+            MatchMode::Exact => "exact",
+            MatchMode::Regex => "regex",
+        }
+    }
+
+    /// The mode after this one, to cycle through them with a key
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+impl Data for MatchMode {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// The fuzzy matchers, created once and shared by every search thread
+pub struct Matchers {
+    skim: SkimMatcherV2,
+    clangd: ClangdMatcher,
+}
+
+impl Matchers {
+    pub fn new(case: CaseMatching) -> Self {
+        let (skim, clangd) = (SkimMatcherV2::default(), ClangdMatcher::default());
+        match case {
+            CaseMatching::Smart => Self {
+                skim: skim.smart_case(),
+                clangd: clangd.smart_case(),
+            },
+            CaseMatching::Ignore => Self {
+                skim: skim.ignore_case(),
+                clangd: clangd.ignore_case(),
+            },
+            CaseMatching::Respect => Self {
+                skim: skim.respect_case(),
+                clangd: clangd.respect_case(),
+            },
+        }
+    }
+}
+
+enum TermKind {
+    Fuzzy,
+    FuzzyClangd,
+    Prefix,
+    Substring,
+    Suffix,
+    Initials,
+    Exact,
+    /// None if the expression doesn't compile, it then matches nothing
+    Regex(Option<Regex>),
+}
+
+struct Term {
+    kind: TermKind,
+    text: Vec<char>,
+    raw: String,
+    negated: bool,
+    case_sensitive: bool,
+}
+
+/// A parsed query. Every group must match, a group matches if any of its
+/// terms does.
+pub struct Pattern {
+    groups: Vec<Vec<Term>>,
+}
+
+impl Pattern {
+    /// Parse a query. With `extended`, it's split on spaces into terms, and
+    /// each term can be modified like in skim:
+    ///
+    /// - `'word` matches a substring
+    /// - `^word` matches a prefix
+    /// - `word$` matches a suffix
+    /// - `!word` must not match (as a substring, or with `^` or `$`)
+    /// - `a | b` matches either term
+    ///
+    /// Regular expressions are never split, spaces and anchors are theirs.
+    ///
+    /// With smart case, each term is matched with case if it has upper case letters.
+    pub fn parse(query: &str, mode: MatchMode, extended: bool, case: CaseMatching) -> Self {
+        if !extended || mode == MatchMode::Regex {
+            let groups = if query.is_empty() {
+                vec![]
+            } else {
+                vec![vec![Term::new(query, mode.into(), false, case)]]
+            };
+            return Self { groups };
+        }
+
+        let mut groups: Vec<Vec<Term>> = Vec::new();
+        let mut alternative = false;
+        for word in query.split_whitespace() {
+            if word == "|" {
+                alternative = !groups.is_empty();
+                continue;
+            }
+            let term = Term::parse(word, mode, case);
+            match groups.last_mut() {
+                Some(group) if alternative => group.push(term),
+                _ => groups.push(vec![term]),
+            }
+            alternative = false;
+        }
+        Self { groups }
+    }
+
+    /// True if everything matching `query` also matched `previous`, so
+    /// only the previous matches need to be searched again.
+    ///
+    /// That's the case when the query was extended, unless negations or
+    /// alternatives are involved ("!fi" excludes more than "!fir"), or
+    /// the mode is one where a longer query can match more.
+    pub fn narrows(previous: &str, query: &str, mode: MatchMode) -> bool {
+        match mode {
+            MatchMode::Exact | MatchMode::Regex => false,
+            _ => {
+                query.starts_with(previous)
+                    && !previous.contains(['!', '$'])
+                    && !query.contains('|')
+            }
+        }
+    }
+
+    /// Score the haystack, and return the char indices that matched
+    pub fn matches(&self, matchers: &Matchers, haystack: &str) -> Option<(i64, Vec<usize>)> {
+        if self.groups.is_empty() {
+            return Some((0, vec![]));
+        }
+        let chars: Vec<char> = haystack.chars().collect();
+        let mut score = 0;
+        let mut indices = Vec::new();
+        for group in &self.groups {
+            let (group_score, group_indices) = group
+                .iter()
+                .filter_map(|term| term.matches(matchers, haystack, &chars))
+                .max_by_key(|(score, _)| *score)?;
+            score += group_score;
+            indices.extend(group_indices);
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        Some((score, indices))
+    }
+}
+
+impl From<MatchMode> for TermKind {
+    fn from(mode: MatchMode) -> Self {
+        match mode {
+            MatchMode::Fuzzy => TermKind::Fuzzy,
+            MatchMode::FuzzyClangd => TermKind::FuzzyClangd,
+            MatchMode::Prefix => TermKind::Prefix,
+            MatchMode::Substring => TermKind::Substring,
+            MatchMode::Initials => TermKind::Initials,
+            MatchMode::Exact => TermKind::Exact,
+            MatchMode::Regex => TermKind::Regex(None),
+        }
+    }
+}
+
+impl Term {
+    fn new(text: &str, kind: TermKind, negated: bool, case: CaseMatching) -> Self {
+        let case_sensitive = case.is_sensitive(text);
+        let kind = match kind {
+            TermKind::Regex(_) => TermKind::Regex(
+                RegexBuilder::new(text)
+                    .case_insensitive(!case_sensitive)
+                    .build()
+                    .ok(),
+            ),
+            kind => kind,
+        };
+        Self {
+            kind,
+            text: text.chars().collect(),
+            raw: text.to_string(),
+            negated,
+            case_sensitive,
+        }
+    }
+
+    fn parse(word: &str, mode: MatchMode, case: CaseMatching) -> Self {
+        let (negated, word) = match word.strip_prefix('!') {
+            Some(word) => (true, word),
+            None => (false, word),
+        };
+        if let Some(word) = word.strip_prefix('\'') {
+            Self::new(word, TermKind::Substring, negated, case)
+        } else if let Some(word) = word.strip_prefix('^') {
+            Self::new(word, TermKind::Prefix, negated, case)
+        } else if word.ends_with('$') && word.len() > 1 {
+            Self::new(&word[..word.len() - 1], TermKind::Suffix, negated, case)
+        } else if negated {
+            // Negated terms are never fuzzy, that would exclude almost everything
+            Self::new(word, TermKind::Substring, negated, case)
+        } else {
+            Self::new(word, mode.into(), negated, case)
+        }
+    }
+
+    fn matches(
+        &self,
+        matchers: &Matchers,
+        haystack: &str,
+        chars: &[char],
+    ) -> Option<(i64, Vec<usize>)> {
+        // A lone "!" or "^" while typing matches everything
+        if self.text.is_empty() {
+            return Some((0, vec![]));
+        }
+        let found = self.find(matchers, haystack, chars);
+        if self.negated {
+            match found {
+                Some(_) => None,
+                None => Some((0, vec![])),
+            }
+        } else {
+            found
+        }
+    }
+
+    fn find(
+        &self,
+        matchers: &Matchers,
+        haystack: &str,
+        chars: &[char],
+    ) -> Option<(i64, Vec<usize>)> {
+        let len = self.text.len();
+        let weight = 10 * len as i64;
+        let starts_with = |chars: &[char], prefix: &[char]| {
+            chars.len() >= prefix.len()
+                && chars
+                    .iter()
+                    .zip(prefix)
+                    .all(|(a, b)| same_char(*a, *b, self.case_sensitive))
+        };
+        match &self.kind {
+            TermKind::Fuzzy => matchers.skim.fuzzy_indices(haystack, &self.raw),
+            TermKind::FuzzyClangd => matchers.clangd.fuzzy_indices(haystack, &self.raw),
+            TermKind::Prefix => {
+                if starts_with(chars, &self.text) {
+                    // Shorter names are closer to what was typed
+                    Some((100 + weight - chars.len() as i64, (0..len).collect()))
+                } else {
+                    None
+                }
+            }
+            TermKind::Substring => {
+                let start = (0..chars.len()).find(|&i| starts_with(&chars[i..], &self.text))?;
+                let bonus = if is_word_start(chars, start) { 50 } else { 0 };
+                Some((
+                    weight + bonus - start as i64,
+                    (start..start + len).collect(),
+                ))
+            }
+            TermKind::Suffix => {
+                let start = chars.len().checked_sub(len)?;
+                if starts_with(&chars[start..], &self.text) {
+                    Some((weight, (start..chars.len()).collect()))
+                } else {
+                    None
+                }
+            }
+            TermKind::Exact => {
+                let start = (0..chars.len()).find(|&i| {
+                    is_word_start(chars, i)
+                        && starts_with(&chars[i..], &self.text)
+                        && is_word_start(chars, i + len)
+                })?;
+                Some((100 + weight - start as i64, (start..start + len).collect()))
+            }
+            TermKind::Initials => {
+                let starts: Vec<usize> = (0..chars.len())
+                    .filter(|&i| is_word_start(chars, i) && chars[i].is_alphanumeric())
+                    .collect();
+                let initials: Vec<char> = starts.iter().map(|&i| chars[i]).collect();
+                let first =
+                    (0..initials.len()).find(|&i| starts_with(&initials[i..], &self.text))?;
+                let bonus = if first == 0 { 50 } else { 0 };
+                Some((weight + bonus, starts[first..first + len].to_vec()))
+            }
+            TermKind::Regex(regex) => {
+                let found = regex.as_ref()?.find(haystack)?;
+                // Regex positions are in bytes, indices are in chars
+                let start = haystack[..found.start()].chars().count();
+                let end = start + haystack[found.start()..found.end()].chars().count();
+                Some((
+                    10 * (end - start) as i64 - start as i64,
+                    (start..end).collect(),
+                ))
+            }
+        }
+    }
+}
+
+fn same_char(a: char, b: char, case_sensitive: bool) -> bool {
+    a == b || (!case_sensitive && a.to_lowercase().eq(b.to_lowercase()))
+}
+
+/// True at the first char of a word, and right after the last one.
+/// Words are split on anything that's not a letter or digit, and on
+/// camel case: "VisualStudio" is two words.
+fn is_word_start(chars: &[char], i: usize) -> bool {
+    if i == 0 || i >= chars.len() {
+        return true;
+    }
+    let (before, current) = (chars[i - 1], chars[i]);
+    if !before.is_alphanumeric() {
+        return true;
+    }
+    if !current.is_alphanumeric() {
+        // The end of a word
+        return true;
+    }
+    before.is_lowercase() && current.is_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: &[&str] = &[
+        "Visual Studio Code",
+        "Firefox Web Browser",
+        "firewall-config",
+        "Files",
+        "GNOME Terminal",
+        "VisualStudioCode",
+    ];
+
+    /// The char indices that match, with smart case and the extended syntax
+    fn find(query: &str, mode: MatchMode, haystack: &str) -> Option<Vec<usize>> {
+        let case = CaseMatching::Smart;
+        Pattern::parse(query, mode, true, case)
+            .matches(&Matchers::new(case), haystack)
+            .map(|(_, indices)| indices)
+    }
+
+    /// The names that match
+    fn names(query: &str, mode: MatchMode) -> Vec<&'static str> {
+        NAMES
+            .iter()
+            .cloned()
+            .filter(|name| find(query, mode, name).is_some())
+            .collect()
+    }
+
+    #[test]
+    fn fuzzy_matches_chars_in_order() {
+        for mode in &[MatchMode::Fuzzy, MatchMode::FuzzyClangd] {
+            assert_eq!(
+                names("fire", *mode),
+                vec!["Firefox Web Browser", "firewall-config"]
+            );
+            assert!(find("erif", *mode, "Firefox").is_none());
+        }
+        assert_eq!(
+            find("vsc", MatchMode::Fuzzy, "Visual Studio Code").map(|i| i.len()),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn prefix_matches_the_start() {
+        assert_eq!(find("fi", MatchMode::Prefix, "Firefox"), Some(vec![0, 1]));
+        assert!(find("fox", MatchMode::Prefix, "Firefox").is_none());
+    }
+
+    #[test]
+    fn substring_matches_anywhere() {
+        assert_eq!(
+            find("fox", MatchMode::Substring, "Firefox"),
+            Some(vec![4, 5, 6])
+        );
+        assert!(find("fxo", MatchMode::Substring, "Firefox").is_none());
+    }
+
+    #[test]
+    fn initials_match_consecutive_words() {
+        assert_eq!(
+            find("vsc", MatchMode::Initials, "Visual Studio Code"),
+            Some(vec![0, 7, 14])
+        );
+        assert_eq!(
+            find("sc", MatchMode::Initials, "VisualStudioCode"),
+            Some(vec![6, 12])
+        );
+        assert!(find("vc", MatchMode::Initials, "Visual Studio Code").is_none());
+    }
+
+    #[test]
+    fn exact_matches_whole_words() {
+        assert_eq!(
+            find("code", MatchMode::Exact, "Visual Studio Code"),
+            Some(vec![14, 15, 16, 17])
+        );
+        assert!(find("cod", MatchMode::Exact, "Visual Studio Code").is_none());
+        assert_eq!(names("config", MatchMode::Exact), vec!["firewall-config"]);
+    }
+
+    #[test]
+    fn regex_is_never_split() {
+        assert_eq!(
+            find("f.re", MatchMode::Regex, "Firefox"),
+            Some(vec![0, 1, 2, 3])
+        );
+        assert_eq!(names("^f.*g$", MatchMode::Regex), vec!["firewall-config"]);
+        assert_eq!(
+            names("web b", MatchMode::Regex),
+            vec!["Firefox Web Browser"]
+        );
+        // Doesn't compile, matches nothing
+        assert!(find("(", MatchMode::Regex, "(").is_none());
+    }
+
+    #[test]
+    fn quote_matches_a_substring() {
+        assert_eq!(
+            find("'fox", MatchMode::Fuzzy, "Firefox"),
+            Some(vec![4, 5, 6])
+        );
+        assert!(find("'frx", MatchMode::Fuzzy, "Firefox").is_none());
+    }
+
+    #[test]
+    fn caret_matches_a_prefix() {
+        assert_eq!(
+            names("^fi", MatchMode::Fuzzy),
+            vec!["Firefox Web Browser", "firewall-config", "Files"]
+        );
+        assert!(find("^fox", MatchMode::Fuzzy, "Firefox").is_none());
+    }
+
+    #[test]
+    fn dollar_matches_a_suffix() {
+        assert_eq!(
+            find("fox$", MatchMode::Fuzzy, "Firefox"),
+            Some(vec![4, 5, 6])
+        );
+        assert!(find("fire$", MatchMode::Fuzzy, "Firefox").is_none());
+        // A lone dollar is just a char
+        assert!(find("$", MatchMode::Substring, "a$b").is_some());
+    }
+
+    #[test]
+    fn bang_excludes() {
+        assert_eq!(
+            names("fire !wall", MatchMode::Fuzzy),
+            vec!["Firefox Web Browser"]
+        );
+        assert_eq!(names("fi !^fire", MatchMode::Fuzzy), vec!["Files"]);
+        assert_eq!(
+            names("fire !config$", MatchMode::Fuzzy),
+            vec!["Firefox Web Browser"]
+        );
+        // Negations are substrings, not fuzzy: "!fwb" doesn't exclude "Firefox Web Browser"
+        assert!(find("!fwb", MatchMode::Fuzzy, "Firefox Web Browser").is_some());
+        // Typing a term after "!" doesn't exclude everything in the meantime
+        assert_eq!(names("!", MatchMode::Fuzzy).len(), NAMES.len());
+    }
+
+    #[test]
+    fn bar_matches_either() {
+        assert_eq!(
+            names("^files | ^gnome", MatchMode::Fuzzy),
+            vec!["Files", "GNOME Terminal"]
+        );
+        // A leading bar has nothing to be an alternative of
+        assert_eq!(names("| ^files", MatchMode::Fuzzy), vec!["Files"]);
+    }
+
+    #[test]
+    fn every_term_must_match() {
+        assert_eq!(
+            names("fire web", MatchMode::Fuzzy),
+            vec!["Firefox Web Browser"]
+        );
+        assert_eq!(
+            find("'web ^fire", MatchMode::Fuzzy, "Firefox Web Browser"),
+            Some(vec![0, 1, 2, 3, 8, 9, 10])
+        );
+    }
+
+    #[test]
+    fn smart_case_respects_upper_case_terms() {
+        assert!(find("fire", MatchMode::Prefix, "Firefox").is_some());
+        assert!(find("FIRE", MatchMode::Prefix, "Firefox").is_none());
+        assert!(find("Fire", MatchMode::Prefix, "Firefox").is_some());
+    }
+
+    #[test]
+    fn without_the_extended_syntax_operators_are_chars() {
+        let case = CaseMatching::Smart;
+        let pattern = Pattern::parse("^fi", MatchMode::Prefix, false, case);
+        assert!(pattern.matches(&Matchers::new(case), "Files").is_none());
+        assert!(pattern.matches(&Matchers::new(case), "^fi").is_some());
+    }
+
+    #[test]
+    fn narrows_when_the_query_grows() {
+        assert!(Pattern::narrows("fi", "fir", MatchMode::Fuzzy));
+        assert!(Pattern::narrows("fi", "fi wall", MatchMode::Fuzzy));
+        assert!(Pattern::narrows("^f", "^fi", MatchMode::Prefix));
+        assert!(Pattern::narrows("fi", "fi !wall", MatchMode::Substring));
+        assert!(Pattern::narrows("v", "vs", MatchMode::Initials));
+    }
+
+    #[test]
+    fn does_not_narrow_otherwise() {
+        // Shorter, or changed
+        assert!(!Pattern::narrows("fir", "fi", MatchMode::Fuzzy));
+        assert!(!Pattern::narrows("fir", "fox", MatchMode::Fuzzy));
+        // A longer negation excludes less
+        assert!(!Pattern::narrows("!fi", "!fir", MatchMode::Fuzzy));
+        // "fi$x" is a fuzzy term, it matches what "fi$" didn't
+        assert!(!Pattern::narrows("fi$", "fi$x", MatchMode::Fuzzy));
+        // Alternatives add matches
+        assert!(!Pattern::narrows("fi", "fi | gnome", MatchMode::Fuzzy));
+        // A longer word or expression can match other names
+        assert!(!Pattern::narrows("fire", "firefox", MatchMode::Exact));
+        assert!(!Pattern::narrows("fi", "fi|gn", MatchMode::Regex));
+    }
+
+    /// Whenever the previous matches are reused, none of the new ones is lost
+    #[test]
+    fn narrowing_never_drops_a_match() {
+        let steps = &[
+            "", "f", "fi", "fi ", "fi !", "fi !w", "fi !wa", "^", "^f", "^fi", "'", "'f", "'fi",
+            "i", "i$", "e", "e s", "e s$", "!",
+        ];
+        for mode in &MatchMode::ALL {
+            for previous in steps {
+                for query in steps {
+                    if !Pattern::narrows(previous, query, *mode) {
+                        continue;
+                    }
+                    for name in NAMES {
+                        if find(query, *mode, name).is_some() {
+                            assert!(
+                                find(previous, *mode, name).is_some(),
+                                "{:?} narrows {:?} in {:?} mode, but only {:?} matches {}",
+                                query,
+                                previous,
+                                mode,
+                                query,
+                                name
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -2,6 +2,147 @@ use druid::{Data, Lens};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+use crate::config::Theme;
+use crate::launch::ResultAction;
+use crate::search::MatchMode;
+
+/// Where a result comes from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Provider {
+    /// An application with a `.desktop` file
+    DesktopEntry,
+    /// An executable in `$PATH`
+    Binary,
+    /// A shell command typed in the query
+    Command,
+    /// An entry from the config
+    Custom,
+    /// A search on the web
+    Web,
+    /// A URL or a path typed in the query
+    Location,
+    /// A conversion or a calculation, copied instead of launched
+    Converter,
+    /// Text copied before, from the clipboard history
+    Clipboard,
+    /// An emoji or another character, copied instead of launched
+    Character,
+    /// A host from the ssh config or known hosts
+    Ssh,
+    /// Locking the screen, logging out, rebooting and the like
+    Session,
+}
+
+impl Provider {
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::DesktopEntry => "application",
+            Provider::Binary => "binary",
+            Provider::Command => "command",
+            Provider::Custom => "custom",
+            Provider::Web => "web",
+            Provider::Location => "location",
+            Provider::Converter => "converter",
+            Provider::Clipboard => "clipboard",
+            Provider::Character => "character",
+            Provider::Ssh => "ssh",
+            Provider::Session => "session",
+        }
+    }
+
+    /// What fuzzle does with the results of each provider
+    fn policy(self) -> Policy {
+        let (cached, copied, has_file, takes_arguments, in_terminal, actions) = match self {
+            Provider::DesktopEntry => (
+                true,
+                false,
+                true,
+                true,
+                false,
+                vec![
+                    ResultAction::CopyDesktopPath,
+                    ResultAction::EditDesktopEntry,
+                ],
+            ),
+            Provider::Binary => (true, false, true, true, false, vec![]),
+            Provider::Command => (false, false, false, true, false, vec![]),
+            Provider::Custom => (true, false, false, true, false, vec![]),
+            Provider::Web => (false, false, false, false, false, vec![]),
+            Provider::Location => (false, false, false, false, false, vec![]),
+            Provider::Converter => (false, true, false, false, false, vec![]),
+            Provider::Clipboard => (
+                false,
+                true,
+                false,
+                false,
+                false,
+                vec![ResultAction::Forget, ResultAction::ClearHistory],
+            ),
+            Provider::Character => (false, true, false, false, false, vec![]),
+            Provider::Ssh => (false, false, false, false, true, vec![]),
+            Provider::Session => (false, false, false, false, false, vec![]),
+        };
+        Policy {
+            cached,
+            copied,
+            has_file,
+            takes_arguments,
+            in_terminal,
+            actions,
+        }
+    }
+
+    /// Whether results of this provider come from the cache, and can be hidden
+    pub fn is_cached(self) -> bool {
+        self.policy().cached
+    }
+
+    /// Whether Enter copies the command of results of this provider,
+    /// instead of running it
+    pub fn is_copied(self) -> bool {
+        self.policy().copied
+    }
+
+    /// Whether the command of results of this provider is a file, with
+    /// a folder to open
+    pub fn has_file(self) -> bool {
+        self.policy().has_file
+    }
+
+    /// Whether results of this provider can be launched with arguments,
+    /// or in a terminal
+    pub fn takes_arguments(self) -> bool {
+        self.policy().takes_arguments
+    }
+
+    /// Whether results of this provider are launched in a terminal, see
+    /// `LaunchConfig::terminal`
+    pub fn in_terminal(self) -> bool {
+        self.policy().in_terminal
+    }
+
+    /// Actions of the menu that only results of this provider have
+    pub fn actions(self) -> Vec<ResultAction> {
+        self.policy().actions
+    }
+}
+
+/// See `Provider::policy`
+struct Policy {
+    cached: bool,
+    copied: bool,
+    has_file: bool,
+    takes_arguments: bool,
+    in_terminal: bool,
+    actions: Vec<ResultAction>,
+}
+
+impl Data for Provider {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
 #[derive(Clone, Debug, Data, PartialEq, Serialize, Deserialize, Lens)]
 pub struct SearchResult {
     pub icon_path: Option<String>,
@@ -9,13 +150,72 @@ pub struct SearchResult {
     pub name: String,
     pub description: String,
     pub command: String,
+    pub provider: Provider,
+    /// Extra words to find the result with, separated by `;`
+    pub keywords: String,
 
     #[serde(skip)]
     pub selected: bool,
+    /// Position in the full list of results
+    #[serde(skip)]
+    pub position: usize,
     #[serde(skip)]
     pub score: i64,
+    /// Matched chars of the name, followed by the desktop file name and keywords
     #[serde(skip)]
     pub indices: Arc<Vec<usize>>,
+    /// Matched chars of the description
+    #[serde(skip)]
+    pub description_indices: Arc<Vec<usize>>,
+}
+
+impl SearchResult {
+    /// A result without an icon, keywords or matches
+    pub fn new(provider: Provider, name: String, description: String, command: String) -> Self {
+        Self {
+            icon_path: None,
+            desktop_entry_path: None,
+            name,
+            description,
+            command,
+            provider,
+            keywords: String::new(),
+            selected: false,
+            position: 0,
+            score: 0,
+            indices: Arc::new(vec![]),
+            description_indices: Arc::new(vec![]),
+        }
+    }
+
+    /// Identifies the result across searches and runs
+    pub fn id(&self) -> String {
+        let id = self.desktop_entry_path.as_ref().unwrap_or(&self.command);
+        format!("{}:{}", self.provider.name(), id)
+    }
+}
+
+/// What the preview pane shows for the selected result
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Preview {
+    /// Identifies the result this is a preview of
+    pub key: String,
+    pub title: String,
+    /// Labelled facts, like `("Exec", "firefox %u")`
+    pub fields: Vec<(String, String)>,
+    /// Longer text, shown wrapped below the fields
+    pub body: String,
+    /// An image to show below everything else
+    pub image: Option<PreviewImage>,
+}
+
+/// A decoded image, already scaled down to fit the preview pane
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PreviewImage {
+    /// RGBA, row by row
+    pub data: Arc<Vec<u8>>,
+    pub width: usize,
+    pub height: usize,
 }
 
 #[derive(Clone, Data, Lens)]
@@ -23,4 +223,13 @@ pub struct AppState {
     pub input_text: String,
     pub search_results: Arc<Vec<SearchResult>>,
     pub selected_line: usize,
+    /// Total number of results, not only the visible ones
+    pub num_results: usize,
+    /// Index of the first visible result
+    pub scroll_offset: usize,
+    pub theme: Arc<Theme>,
+    pub match_mode: MatchMode,
+    /// Details about the selected result
+    pub detail: String,
+    pub preview: Arc<Preview>,
 }
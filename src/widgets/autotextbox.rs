@@ -1,45 +1,58 @@
 //! A textbox widget that keeps focus.
-use druid::kurbo::{Affine, Line, Point, RoundedRect, Size, Vec2};
+use druid::kurbo::{Affine, Line, Point, Rect, RoundedRect, Size, Vec2};
 use druid::piet::{
     FontBuilder, PietText, PietTextLayout, RenderContext, Text, TextLayout, TextLayoutBuilder,
 };
 use druid::widget::TextBox;
 use druid::{
-    theme, BoxConstraints, Env, Event, EventCtx, HotKey, KeyCode, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, Selector, SysMods, TimerToken, UpdateCtx, Widget,
+    theme, Application, BoxConstraints, Command, Env, Event, EventCtx, KeyCode, KeyEvent,
+    LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Selector, TimerToken, UpdateCtx, Widget,
 };
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-const PADDING_TOP: f64 = 30.;
-const PADDING_LEFT: f64 = 30.;
+use super::editing::{self, Selection};
+use crate::config::{
+    Action, Keymap, CURSOR_COLOR, INPUT_FONT_SIZE, PADDING, ROW_HEIGHT, SELECTION_COLOR,
+};
+
 const RESET_BLINK: Selector = Selector::new("reset-autotextbox-blink");
+/// Sent with the new text as a `String`, every time the text changes
+pub const QUERY_CHANGED: Selector = Selector::new("fuzzle.query-changed");
+/// Sent when Right is pressed at the end of the text, to open the actions menu
+pub const ACTIONS_REQUESTED: Selector = Selector::new("fuzzle.actions-requested");
 
 /// A widget that allows user text input.
 pub struct AutoTextBox {
     textbox: Box<TextBox>,
+    keymap: Arc<Keymap>,
     width: f64,
     hscroll_offset: f64,
     cursor_timer: TimerToken,
     cursor_on: bool,
-    cursor: usize,
+    selection: Selection,
+    /// The text as this widget last left it, to tell when it's changed from outside
+    text: String,
 }
 
 impl AutoTextBox {
     /// Create a new AutoTextBox widget
-    pub fn new() -> Self {
+    pub fn new(keymap: Arc<Keymap>) -> Self {
         Self {
             textbox: Box::new(TextBox::raw()),
+            keymap,
             width: 0.0,
             hscroll_offset: 0.,
             cursor_timer: TimerToken::INVALID,
-            cursor: 0,
+            selection: Selection::default(),
             cursor_on: true,
+            text: String::new(),
         }
     }
 
     fn get_layout(&self, piet_text: &mut PietText, text: &str, env: &Env) -> PietTextLayout {
         let font_name = env.get(theme::FONT_NAME);
-        let font_size = 35.;
+        let font_size = env.get(INPUT_FONT_SIZE);
         let font = piet_text
             .new_font_by_name(font_name, font_size)
             .build()
@@ -62,11 +75,11 @@ impl AutoTextBox {
     }
 
     /// Calculate a stateful scroll offset
-    fn update_hscroll(&mut self, layout: &PietTextLayout) {
-        let cursor_x = self.x_for_offset(layout, self.cursor);
+    fn update_hscroll(&mut self, layout: &PietTextLayout, env: &Env) {
+        let cursor_x = self.x_for_offset(layout, self.selection.cursor);
         let overall_text_width = layout.width();
 
-        let padding = PADDING_LEFT * 2.;
+        let padding = Self::padding(env) * 2.;
         if overall_text_width < self.width {
             // There's no offset if text is smaller than text box
             //
@@ -88,6 +101,110 @@ impl AutoTextBox {
         }
     }
 
+    /// The text is a bit more indented than the list elements
+    fn padding(env: &Env) -> f64 {
+        env.get(PADDING) * 1.5
+    }
+
+    /// Look up the action for a key event. Bindings pressed with shift, that
+    /// are not bound with shift themselves, extend the selection.
+    fn action(&self, key_event: &KeyEvent) -> (Option<Action>, bool) {
+        match self.keymap.actions(key_event).first() {
+            Some(action) => (Some(*action), false),
+            None if key_event.mods.shift => {
+                let mut unshifted = key_event.clone();
+                unshifted.mods.shift = false;
+                match self.keymap.actions(&unshifted).first() {
+                    Some(Action::LineStart) => (Some(Action::LineStart), true),
+                    Some(Action::LineEnd) => (Some(Action::LineEnd), true),
+                    _ => (None, false),
+                }
+            }
+            None => (None, false),
+        }
+    }
+
+    /// Handle the keys that are not in the keymap: typing, deleting and
+    /// moving the cursor.
+    fn edit(&mut self, key_event: &KeyEvent, data: &mut String) {
+        let selecting = key_event.mods.shift;
+        let by_word = key_event.mods.ctrl;
+        let cursor = self.selection.cursor;
+        match key_event.key_code {
+            KeyCode::ArrowLeft => {
+                let offset = if by_word {
+                    editing::prev_word(data, cursor)
+                } else if !selecting && !self.selection.is_caret() {
+                    self.selection.range().start
+                } else {
+                    editing::prev_grapheme(data, cursor)
+                };
+                self.selection.move_to(offset, selecting);
+            }
+            KeyCode::ArrowRight => {
+                let offset = if by_word {
+                    editing::next_word(data, cursor)
+                } else if !selecting && !self.selection.is_caret() {
+                    self.selection.range().end
+                } else {
+                    editing::next_grapheme(data, cursor)
+                };
+                self.selection.move_to(offset, selecting);
+            }
+            KeyCode::Backspace => {
+                if self.selection.is_caret() {
+                    self.selection.anchor = editing::prev_grapheme(data, cursor);
+                }
+                editing::delete(data, &mut self.selection);
+            }
+            KeyCode::Delete => {
+                if self.selection.is_caret() {
+                    self.selection.anchor = editing::next_grapheme(data, cursor);
+                }
+                editing::delete(data, &mut self.selection);
+            }
+            key_code
+                if key_code.is_printable()
+                    && !key_event.mods.ctrl
+                    && !key_event.mods.alt
+                    && !key_event.mods.meta =>
+            {
+                if let Some(text) = key_event.text() {
+                    editing::insert(data, &mut self.selection, text);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Right, without modifiers, with the caret after the last character
+    fn at_end(&self, key_event: &KeyEvent, data: &str) -> bool {
+        let mods = key_event.mods;
+        key_event.key_code == KeyCode::ArrowRight
+            && !(mods.shift || mods.ctrl || mods.alt || mods.meta)
+            && self.selection.is_caret()
+            && self.selection.cursor == data.len()
+    }
+
+    /// Put the selected text in the clipboard, and remove it if `cut`
+    fn copy(&mut self, data: &mut String, cut: bool) {
+        if self.selection.is_caret() {
+            return;
+        }
+        Application::clipboard().put_string(&data[self.selection.range()]);
+        if cut {
+            editing::delete(data, &mut self.selection);
+        }
+    }
+
+    fn paste(&mut self, data: &mut String) {
+        if let Some(text) = Application::clipboard().get_string() {
+            // This is a single line input
+            let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+            editing::insert(data, &mut self.selection, &text);
+        }
+    }
+
     fn reset_cursor_blink(&mut self, ctx: &mut EventCtx) {
         self.cursor_on = true;
         let deadline = Instant::now() + Duration::from_millis(500);
@@ -104,34 +221,40 @@ impl Widget<String> for AutoTextBox {
                 ctx.request_focus();
             }
             Event::KeyDown(key_event) => {
-                match key_event {
-                    // TODO: Ignoring commands here because if I just
-                    //       avoid propagating events in the delegate
-                    //       the app does not repaint, but that means
-                    //       autotextbox need to know about global keybindings
-                    ke if ke.key_code == KeyCode::ArrowDown
-                        || ke.key_code == KeyCode::ArrowUp
-                        || (HotKey::new(SysMods::Cmd, "j")).matches(ke)
-                        || (HotKey::new(SysMods::Cmd, "k")).matches(ke)
-                        || (HotKey::new(SysMods::Cmd, "n")).matches(ke)
-                        || (HotKey::new(SysMods::Cmd, "p")).matches(ke) => {}
-                    // Only allow some of the textbox events, I really only
-                    // need to write and delete with backspace, no selection,
-                    // movement, copy, paste or other stuff.
-                    k_e if k_e.key_code.is_printable() => {
-                        let incoming_text = k_e.text().unwrap_or("");
-                        self.cursor = data.len() + incoming_text.len();
-                        self.reset_cursor_blink(ctx);
-                        self.textbox.event(ctx, event, data, env);
+                let old_text = data.clone();
+                match self.action(key_event) {
+                    (Some(Action::DeleteWord), _) => {
+                        if self.selection.is_caret() {
+                            self.selection.anchor = editing::prev_word(data, self.selection.cursor);
+                        }
+                        editing::delete(data, &mut self.selection);
+                    }
+                    (Some(Action::Clear), _) => {
+                        data.clear();
+                        self.selection = Selection::caret(0);
+                    }
+                    (Some(Action::LineStart), selecting) => self.selection.move_to(0, selecting),
+                    (Some(Action::LineEnd), selecting) => {
+                        self.selection.move_to(data.len(), selecting)
                     }
-                    k_e if (HotKey::new(None, KeyCode::Backspace)).matches(k_e) => {
-                        self.textbox.event(ctx, event, data, env);
+                    (Some(Action::Copy), _) => self.copy(data, false),
+                    (Some(Action::Cut), _) => self.copy(data, true),
+                    (Some(Action::Paste), _) => self.paste(data),
+                    // Any other bound key is handled by the delegate
+                    (Some(_), _) => {}
+                    (None, _) if self.at_end(key_event, data) => {
+                        ctx.submit_command(ACTIONS_REQUESTED, None)
                     }
-                    _ => (),
+                    (None, _) => self.edit(key_event, data),
                 };
+                self.reset_cursor_blink(ctx);
+                if *data != old_text {
+                    ctx.submit_command(Command::new(QUERY_CHANGED, data.clone()), None);
+                }
+                self.text = data.clone();
 
                 let text_layout = self.get_layout(&mut ctx.text(), &data, env);
-                self.update_hscroll(&text_layout);
+                self.update_hscroll(&text_layout, env);
                 ctx.request_paint();
             }
             Event::Command(cmd) if cmd.selector == RESET_BLINK => self.reset_cursor_blink(ctx),
@@ -152,6 +275,11 @@ impl Widget<String> for AutoTextBox {
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &String, data: &String, env: &Env) {
+        // Text set from outside, like a completion, is continued at the end
+        if *data != self.text {
+            self.selection = Selection::caret(data.len());
+            self.text = data.clone();
+        }
         self.textbox.update(ctx, old_data, data, env);
         ctx.request_paint();
     }
@@ -173,8 +301,9 @@ impl Widget<String> for AutoTextBox {
     }
 
     fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &String, env: &Env) {
-        let font_size = 35.;
-        let height = 75.;
+        let font_size = env.get(INPUT_FONT_SIZE);
+        let height = env.get(ROW_HEIGHT);
+        let padding = Self::padding(env);
         let text_color = env.get(theme::LABEL_COLOR);
 
         // Paint the background
@@ -197,19 +326,33 @@ impl Widget<String> for AutoTextBox {
 
                 // Layout, measure, and draw text
                 let text_height = font_size * 0.8;
-                let text_pos = Point::new(0.0 + PADDING_LEFT, text_height + PADDING_TOP);
+                let text_pos = Point::new(0.0 + padding, text_height + padding);
                 let color = &text_color;
 
+                // Paint the selection behind the text
+                if !self.selection.is_caret() {
+                    let range = self.selection.range();
+                    let start_x = self.x_for_offset(&text_layout, range.start);
+                    let end_x = self.x_for_offset(&text_layout, range.end);
+                    let selection_rect = Rect::new(
+                        text_pos.x + start_x,
+                        text_pos.y - font_size,
+                        text_pos.x + end_x,
+                        text_pos.y + font_size * 0.2,
+                    );
+                    rc.fill(selection_rect, &env.get(SELECTION_COLOR));
+                }
+
                 rc.draw_text(&text_layout, text_pos, color);
 
                 // Paint the cursor
                 if self.cursor_on {
-                    let cursor_x = self.x_for_offset(&text_layout, self.cursor);
+                    let cursor_x = self.x_for_offset(&text_layout, self.selection.cursor);
                     let xy = text_pos + Vec2::new(cursor_x, 2. - font_size);
                     let x2y2 = xy + Vec2::new(0., font_size + 2.);
                     let line = Line::new(xy, x2y2);
 
-                    rc.stroke(line, &text_color, 1.);
+                    rc.stroke(line, &env.get(CURSOR_COLOR), 1.);
                 }
                 Ok(())
             })
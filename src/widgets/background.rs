@@ -0,0 +1,118 @@
+//! Paints the theme background color and image behind the whole window.
+use druid::kurbo::{Rect, Size};
+use druid::piet::{ImageFormat, InterpolationMode, RenderContext};
+use druid::{
+    theme, BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, UpdateCtx, Widget,
+};
+use image::GenericImageView;
+
+use crate::config::expand_home;
+use crate::AppState;
+
+/// Decoded background image, kept around so we only decode it again
+/// when the theme points to a different file.
+struct BackgroundImage {
+    path: String,
+    data: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+pub struct Background {
+    child: Box<dyn Widget<AppState>>,
+    image: Option<BackgroundImage>,
+}
+
+impl Background {
+    pub fn new(child: impl Widget<AppState> + 'static) -> Self {
+        Self {
+            child: Box::new(child),
+            image: None,
+        }
+    }
+
+    fn resolve_image(&mut self, data: &AppState) {
+        let path = match &data.theme.background_image {
+            Some(path) => path,
+            None => {
+                self.image = None;
+                return;
+            }
+        };
+        if self.image.as_ref().map(|im| &im.path) == Some(path) {
+            return;
+        }
+        self.image = match image::open(expand_home(path)) {
+            Ok(im) => {
+                let (width, height) = im.dimensions();
+                Some(BackgroundImage {
+                    path: path.clone(),
+                    data: im.to_rgba().into_raw(),
+                    width: width as usize,
+                    height: height as usize,
+                })
+            }
+            Err(_) => None,
+        };
+    }
+}
+
+impl Widget<AppState> for Background {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut AppState, env: &Env) {
+        self.child.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &AppState, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &AppState, data: &AppState, env: &Env) {
+        if !old_data.theme.same(&data.theme) {
+            ctx.request_paint();
+        }
+        self.child.update(ctx, old_data, data, env)
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &AppState,
+        env: &Env,
+    ) -> Size {
+        self.child.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &AppState, env: &Env) {
+        let rect = Rect::from_origin_size((0., 0.), paint_ctx.size());
+        paint_ctx.fill(rect, &env.get(theme::WINDOW_BACKGROUND_COLOR));
+
+        self.resolve_image(data);
+        if let Some(im) = &self.image {
+            if let Ok(image) =
+                paint_ctx.make_image(im.width, im.height, &im.data, ImageFormat::RgbaSeparate)
+            {
+                // Scale the image to cover the whole window, cropping what's left out
+                let size = paint_ctx.size();
+                let scale = (size.width / im.width as f64).max(size.height / im.height as f64);
+                let image_size = Size::new(im.width as f64 * scale, im.height as f64 * scale);
+                let origin = (
+                    (size.width - image_size.width) / 2.,
+                    (size.height - image_size.height) / 2.,
+                );
+                let _ = paint_ctx.with_save(|rc| {
+                    rc.clip(rect);
+                    rc.draw_image(
+                        &image,
+                        Rect::from_origin_size(origin, image_size),
+                        InterpolationMode::Bilinear,
+                    );
+                    Ok(())
+                });
+            }
+        }
+
+        self.child.paint(paint_ctx, data, env);
+    }
+}
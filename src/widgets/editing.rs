@@ -0,0 +1,202 @@
+//! Cursor movement and editing helpers for a single line of text.
+//!
+//! All offsets are in bytes, and always sit on grapheme cluster boundaries.
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The cursor and the other end of the selection.
+/// When they are the same there's no selection, just a caret.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Selection {
+    pub anchor: usize,
+    pub cursor: usize,
+}
+
+impl Selection {
+    pub fn caret(offset: usize) -> Self {
+        Self {
+            anchor: offset,
+            cursor: offset,
+        }
+    }
+
+    pub fn is_caret(&self) -> bool {
+        self.anchor == self.cursor
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.anchor.min(self.cursor)..self.anchor.max(self.cursor)
+    }
+
+    /// Move the cursor, extending the selection if `selecting`
+    pub fn move_to(&mut self, offset: usize, selecting: bool) {
+        self.cursor = offset;
+        if !selecting {
+            self.anchor = offset;
+        }
+    }
+}
+
+/// The first grapheme boundary at or after the offset
+pub fn snap(text: &str, offset: usize) -> usize {
+    text.grapheme_indices(true)
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .find(|boundary| *boundary >= offset)
+        .unwrap_or(text.len())
+}
+
+pub fn prev_grapheme(text: &str, offset: usize) -> usize {
+    text[..offset]
+        .grapheme_indices(true)
+        .next_back()
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+pub fn next_grapheme(text: &str, offset: usize) -> usize {
+    text[offset..]
+        .graphemes(true)
+        .next()
+        .map(|g| offset + g.len())
+        .unwrap_or(offset)
+}
+
+fn is_space(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+/// Start of the word before the offset, skipping whitespace
+pub fn prev_word(text: &str, offset: usize) -> usize {
+    let mut start = offset;
+    let mut in_word = false;
+    for (i, grapheme) in text[..offset].grapheme_indices(true).rev() {
+        if in_word && is_space(grapheme) {
+            break;
+        }
+        in_word |= !is_space(grapheme);
+        start = i;
+    }
+    start
+}
+
+/// End of the word after the offset, skipping whitespace
+pub fn next_word(text: &str, offset: usize) -> usize {
+    let mut end = offset;
+    let mut in_word = false;
+    for (i, grapheme) in text[offset..].grapheme_indices(true) {
+        if in_word && is_space(grapheme) {
+            break;
+        }
+        in_word |= !is_space(grapheme);
+        end = offset + i + grapheme.len();
+    }
+    end
+}
+
+/// Replace the selected text, leaving a caret after what was inserted.
+/// What was inserted can merge with the next grapheme, like a letter
+/// before a combining accent, then the caret goes after both.
+pub fn insert(text: &mut String, selection: &mut Selection, new_text: &str) {
+    let range = selection.range();
+    text.replace_range(range.clone(), new_text);
+    *selection = Selection::caret(snap(text, range.start + new_text.len()));
+}
+
+/// Delete the selected text, leaving a caret where it was
+pub fn delete(text: &mut String, selection: &mut Selection) {
+    insert(text, selection, "");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An e and a combining acute accent, one grapheme of three bytes
+    const E_ACUTE: &str = "e\u{301}";
+    /// A family, four emoji joined by zero width joiners
+    const FAMILY: &str = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+
+    #[test]
+    fn graphemes_are_stepped_over_whole() {
+        let text = format!("a{}b{}", E_ACUTE, FAMILY);
+        assert_eq!(next_grapheme(&text, 0), 1);
+        assert_eq!(next_grapheme(&text, 1), 4);
+        assert_eq!(next_grapheme(&text, 5), text.len());
+        assert_eq!(next_grapheme(&text, text.len()), text.len());
+        assert_eq!(prev_grapheme(&text, text.len()), 5);
+        assert_eq!(prev_grapheme(&text, 4), 1);
+        assert_eq!(prev_grapheme(&text, 0), 0);
+    }
+
+    #[test]
+    fn snap_moves_to_the_end_of_the_grapheme() {
+        let text = format!("a{}", E_ACUTE);
+        assert_eq!(snap(&text, 0), 0);
+        assert_eq!(snap(&text, 1), 1);
+        assert_eq!(snap(&text, 2), text.len());
+        assert_eq!(snap(FAMILY, 4), FAMILY.len());
+    }
+
+    #[test]
+    fn words_skip_whitespace_first() {
+        let text = "firefox  --new-window   example";
+        assert_eq!(prev_word(text, text.len()), 24);
+        assert_eq!(prev_word(text, 24), 9);
+        assert_eq!(prev_word(text, 3), 0);
+        assert_eq!(prev_word(text, 0), 0);
+        assert_eq!(next_word(text, 0), 7);
+        assert_eq!(next_word(text, 7), 21);
+        assert_eq!(next_word(text, 21), text.len());
+    }
+
+    #[test]
+    fn words_keep_graphemes_whole() {
+        let text = format!("caf{} {}", E_ACUTE, FAMILY);
+        assert_eq!(prev_word(&text, text.len()), 7);
+        assert_eq!(next_word(&text, 0), 6);
+    }
+
+    #[test]
+    fn insert_replaces_the_selection() {
+        let mut text = "hello world".to_string();
+        let mut selection = Selection {
+            anchor: 11,
+            cursor: 6,
+        };
+        insert(&mut text, &mut selection, "there");
+        assert_eq!(text, "hello there");
+        assert_eq!(selection, Selection::caret(11));
+
+        let mut selection = Selection::caret(0);
+        insert(&mut text, &mut selection, "oh ");
+        assert_eq!(text, "oh hello there");
+        assert_eq!(selection, Selection::caret(3));
+    }
+
+    #[test]
+    fn insert_before_a_combining_mark_puts_the_caret_after_it() {
+        let mut text = "\u{301}x".to_string();
+        let mut selection = Selection::caret(0);
+        insert(&mut text, &mut selection, "e");
+        assert_eq!(text, format!("{}x", E_ACUTE));
+        assert_eq!(selection, Selection::caret(E_ACUTE.len()));
+    }
+
+    #[test]
+    fn delete_removes_the_selection_only() {
+        let mut text = "hello world".to_string();
+        let mut selection = Selection {
+            anchor: 5,
+            cursor: 11,
+        };
+        delete(&mut text, &mut selection);
+        assert_eq!(text, "hello");
+        assert_eq!(selection, Selection::caret(5));
+
+        let mut selection = Selection::caret(2);
+        delete(&mut text, &mut selection);
+        assert_eq!(text, "hello");
+        assert_eq!(selection, Selection::caret(2));
+    }
+}
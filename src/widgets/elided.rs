@@ -0,0 +1,94 @@
+//! A line of text cut in the middle when it doesn't fit, the ends of
+//! commands and paths say the most.
+use druid::kurbo::Size;
+use druid::piet::{
+    FontBuilder, PietText, PietTextLayout, RenderContext, Text, TextLayout, TextLayoutBuilder,
+};
+use druid::{
+    theme, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    UpdateCtx, Widget,
+};
+
+/// Like druid's labels: lines are this much taller than the font size,
+/// and the baseline is this far down
+const LINE_HEIGHT_FACTOR: f64 = 1.2;
+const BASELINE_FACTOR: f64 = 0.8;
+
+/// Takes all the width it's given, and elides its text to fit it
+pub struct ElidedLabel;
+
+impl ElidedLabel {
+    pub fn new() -> Self {
+        ElidedLabel
+    }
+}
+
+fn text_layout(piet_text: &mut PietText, text: &str, env: &Env) -> PietTextLayout {
+    let font = piet_text
+        .new_font_by_name(env.get(theme::FONT_NAME), env.get(theme::TEXT_SIZE_NORMAL))
+        .build()
+        .unwrap();
+    piet_text.new_text_layout(&font, text).build().unwrap()
+}
+
+/// Cut the middle out of a text longer than `max_chars`
+fn elide(text: &str, max_chars: usize) -> String {
+    let len = text.chars().count();
+    if len <= max_chars {
+        return text.to_string();
+    }
+    let keep = max_chars.saturating_sub(1);
+    let head: String = text.chars().take(keep / 2).collect();
+    let tail: String = text.chars().skip(len - (keep - keep / 2)).collect();
+    format!("{}…{}", head, tail)
+}
+
+impl Widget<String> for ElidedLabel {
+    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut String, _env: &Env) {}
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &String,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &String, data: &String, _env: &Env) {
+        if old_data != data {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &String,
+        env: &Env,
+    ) -> Size {
+        let height = env.get(theme::TEXT_SIZE_NORMAL) * LINE_HEIGHT_FACTOR;
+        bc.constrain(Size::new(bc.max().width, height))
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &String, env: &Env) {
+        let width = paint_ctx.size().width;
+        let mut layout = text_layout(paint_ctx.text(), data, env);
+        if layout.width() > width {
+            // The most chars that fit, by bisection
+            let (mut low, mut high) = (0, data.chars().count());
+            while low < high {
+                let mid = (low + high + 1) / 2;
+                if text_layout(paint_ctx.text(), &elide(data, mid), env).width() <= width {
+                    low = mid;
+                } else {
+                    high = mid - 1;
+                }
+            }
+            layout = text_layout(paint_ctx.text(), &elide(data, low), env);
+        }
+        let baseline = env.get(theme::TEXT_SIZE_NORMAL) * BASELINE_FACTOR;
+        paint_ctx.draw_text(&layout, (0., baseline), &env.get(theme::LABEL_COLOR));
+    }
+}
@@ -1,29 +1,90 @@
 use druid::kurbo::{Point, Rect, Size};
 use druid::piet::{
-    CairoTextLayout, Color, FontBuilder, InterpolationMode, PietText, RenderContext, Text,
+    CairoFont, CairoTextLayout, FontBuilder, InterpolationMode, PietText, RenderContext, Text,
     TextLayout, TextLayoutBuilder, UnitPoint,
 };
 use druid::{
-    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, UpdateCtx,
-    Widget,
+    theme, BoxConstraints, Color, Command, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, MouseButton, PaintCtx, Selector, UpdateCtx, Widget,
 };
 use image::GenericImageView;
 
+use crate::config::{
+    DESCRIPTION_FONT_SIZE, HIGHLIGHT_COLOR, HOVER_COLOR, MAIN_COLOR, NAME_FONT_SIZE, PADDING,
+    ROW_HEIGHT, SECONDARY_COLOR, SELECTION_COLOR,
+};
+use crate::state::Provider;
 use crate::SearchResult;
 
-const MAIN_COLOR: Color = Color::rgb8(0xc2, 0xc2, 0xc2);
-const SECONDARY_COLOR: Color = Color::rgb8(0x92, 0x92, 0x92);
-const PADDING: f64 = 20.;
+/// Sent with the result position and the click count, as `(usize, u32)`
+pub const RESULT_CLICKED: Selector = Selector::new("fuzzle.result-clicked");
+/// Sent with the number of lines to scroll the results by, as `isize`
+pub const RESULTS_SCROLLED: Selector = Selector::new("fuzzle.results-scrolled");
+
+/// Characters are drawn in place of the icon, this big
+const GLYPH_SIZE: f64 = 36.;
+/// Icons are drawn this big, the glyph is centered on the same spot
+const ICON_SIZE: f64 = 48.;
+/// Where icons go, right of the padding
+const ICON_LEFT: f64 = 8.;
+const ICON_TOP: f64 = 14.;
+/// Height of a line and its baseline, over the font size, like druid's labels
+const LINE_HEIGHT_FACTOR: f64 = 1.2;
+const BASELINE_FACTOR: f64 = 0.8;
+
+/// The square that icons and glyphs are drawn in
+fn icon_slot(padding: f64) -> Rect {
+    Rect::from_origin_size((padding + ICON_LEFT, ICON_TOP), (ICON_SIZE, ICON_SIZE))
+}
+
+/// Text split in runs of plain and matched characters, each with its own
+/// layout, since a layout can only have one color
+struct Segments(Vec<(CairoTextLayout, bool)>);
+
+impl Segments {
+    /// `indices` are the matched char indices, those past the end of `text` are ignored
+    fn new(piet_text: &mut PietText, font: &CairoFont, text: &str, indices: &[usize]) -> Self {
+        let mut runs: Vec<(String, bool)> = Vec::new();
+        for (i, c) in text.chars().enumerate() {
+            let matched = indices.binary_search(&i).is_ok();
+            match runs.last_mut() {
+                Some((run, run_matched)) if *run_matched == matched => run.push(c),
+                _ => runs.push((c.to_string(), matched)),
+            }
+        }
+        let layouts = runs
+            .into_iter()
+            .map(|(run, matched)| {
+                let layout = piet_text.new_text_layout(font, &run).build().unwrap();
+                (layout, matched)
+            })
+            .collect();
+        Segments(layouts)
+    }
+
+    fn width(&self) -> f64 {
+        self.0.iter().map(|(layout, _)| layout.width()).sum()
+    }
+
+    fn draw(&self, paint_ctx: &mut PaintCtx, origin: Point, color: &Color, highlight: &Color) {
+        let mut x = origin.x;
+        for (layout, matched) in &self.0 {
+            let color = if *matched { highlight } else { color };
+            paint_ctx.draw_text(layout, (x, origin.y), color);
+            x += layout.width();
+        }
+    }
+}
 
 /// A list element that displays a searchresult
 pub struct ListElement {
-    name: Option<CairoTextLayout>,
-    name_font_size: f64,
-    description: Option<CairoTextLayout>,
-    description_font_size: f64,
+    name: Option<Segments>,
+    description: Option<Segments>,
     icon_data: Option<Vec<u8>>,
     icon_width: usize,
     icon_height: usize,
+    /// The character itself, for results of the characters provider
+    glyph: Option<CairoTextLayout>,
     selected: bool,
 }
 
@@ -31,12 +92,11 @@ impl ListElement {
     pub fn new() -> Self {
         Self {
             name: None,
-            name_font_size: 20.,
             description: None,
-            description_font_size: 17.,
             icon_data: None,
             icon_height: 0,
             icon_width: 0,
+            glyph: None,
             selected: false,
         }
     }
@@ -61,46 +121,77 @@ impl ListElement {
         self.icon_height = height as usize;
     }
 
-    fn resolve(&mut self, piet_text: &mut PietText, data: &SearchResult) {
-        self.resolve_icon(data);
-        let font_name = "sans-serif";
+    fn resolve(&mut self, piet_text: &mut PietText, data: &SearchResult, env: &Env) {
+        let font_name = env.get(theme::FONT_NAME);
+        if data.provider == Provider::Character {
+            let font = piet_text
+                .new_font_by_name(font_name, GLYPH_SIZE)
+                .build()
+                .unwrap();
+            self.glyph = Some(
+                piet_text
+                    .new_text_layout(&font, &data.command)
+                    .build()
+                    .unwrap(),
+            );
+            self.icon_data = None;
+        } else {
+            self.glyph = None;
+            self.resolve_icon(data);
+        }
 
         let name_font = piet_text
-            .new_font_by_name(font_name, self.name_font_size)
+            .new_font_by_name(font_name, env.get(NAME_FONT_SIZE))
             .build()
             .unwrap();
 
         let description_font = piet_text
-            .new_font_by_name(font_name, self.description_font_size)
+            .new_font_by_name(font_name, env.get(DESCRIPTION_FONT_SIZE))
             .build()
             .unwrap();
-        self.name = Some(
-            piet_text
-                .new_text_layout(&name_font, &data.name)
-                .build()
-                .unwrap(),
-        );
-        self.description = Some(
-            piet_text
-                .new_text_layout(&description_font, &data.description)
-                .build()
-                .unwrap(),
-        );
+        // Indices past the name are in the desktop file name, which isn't shown
+        self.name = Some(Segments::new(
+            piet_text,
+            &name_font,
+            &data.name,
+            &data.indices,
+        ));
+        self.description = Some(Segments::new(
+            piet_text,
+            &description_font,
+            &data.description,
+            &data.description_indices,
+        ));
         self.selected = data.selected;
     }
 }
 
 impl Widget<SearchResult> for ListElement {
-    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut SearchResult, _env: &Env) {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut SearchResult, _env: &Env) {
+        // Selection lives in AppState, so we ask the delegate to change it
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                let click = (data.position, mouse.count);
+                ctx.submit_command(Command::new(RESULT_CLICKED, click), None);
+            }
+            Event::Wheel(wheel) if wheel.delta.y != 0. => {
+                let lines: isize = if wheel.delta.y > 0. { 1 } else { -1 };
+                ctx.submit_command(Command::new(RESULTS_SCROLLED, lines), None);
+            }
+            _ => (),
+        }
     }
 
     fn lifecycle(
         &mut self,
-        _ctx: &mut LifeCycleCtx,
-        _event: &LifeCycle,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
         _data: &SearchResult,
         _env: &Env,
     ) {
+        if let LifeCycle::HotChanged(_) = event {
+            ctx.request_paint();
+        }
     }
 
     fn update(
@@ -118,22 +209,24 @@ impl Widget<SearchResult> for ListElement {
         _layout_ctx: &mut LayoutCtx,
         bc: &BoxConstraints,
         _data: &SearchResult,
-        _env: &Env,
+        env: &Env,
     ) -> Size {
         bc.debug_check("ListElement");
+        let height = env.get(ROW_HEIGHT);
         if let Some(name) = &self.name {
             if let Some(description) = &self.description {
-                bc.constrain(Size::new(name.width().max(description.width()), 75.))
+                bc.constrain(Size::new(name.width().max(description.width()), height))
             } else {
-                bc.constrain(Size::new(name.width(), 75.))
+                bc.constrain(Size::new(name.width(), height))
             }
         } else {
-            bc.constrain(Size::new(0., 75.))
+            bc.constrain(Size::new(0., height))
         }
     }
 
-    fn paint(&mut self, paint_ctx: &mut PaintCtx, search_result: &SearchResult, _env: &Env) {
-        self.resolve(paint_ctx.text(), search_result);
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, search_result: &SearchResult, env: &Env) {
+        self.resolve(paint_ctx.text(), search_result, env);
+        let padding = env.get(PADDING);
         if let Some(data) = &self.icon_data {
             let image = match paint_ctx.make_image(
                 self.icon_width,
@@ -148,41 +241,67 @@ impl Widget<SearchResult> for ListElement {
             paint_ctx.draw_image(
                 &image,
                 Rect::from_origin_size(
-                    Point::from((PADDING + 8., 14.)),
+                    icon_slot(padding).origin(),
                     (self.icon_width as f64, self.icon_height as f64),
                 ),
                 InterpolationMode::Bilinear,
             );
         }
 
-        if self.selected {
+        // Centered in the icon slot, with its line box
+        if let Some(glyph) = &self.glyph {
+            let slot = icon_slot(padding);
+            let x = slot.x0 + (slot.width() - glyph.width()).max(0.) / 2.;
+            let top = slot.center().y - GLYPH_SIZE * LINE_HEIGHT_FACTOR / 2.;
+            let baseline = top + GLYPH_SIZE * BASELINE_FACTOR;
+            paint_ctx.draw_text(glyph, (x, baseline), &env.get(MAIN_COLOR));
+        }
+
+        let highlight = if self.selected {
+            Some(env.get(SELECTION_COLOR))
+        } else if paint_ctx.is_hot() {
+            Some(env.get(HOVER_COLOR))
+        } else {
+            None
+        };
+        if let Some(color) = highlight {
             let width = paint_ctx.size().width;
             let height = paint_ctx.size().height;
             paint_ctx.fill(
                 Rect::from_origin_size(Point::ORIGIN, Size::new(width, height)),
-                &Color::rgba8(0xff, 0xff, 0xff, 0x22),
+                &color,
             )
         }
         if let Some(name) = &self.name {
             let name_origin = UnitPoint::LEFT.resolve(Rect::from_origin_size(
-                Point::from((PADDING + 64., 16.)),
+                Point::from((padding + 64., 16.)),
                 Size::new(
                     (paint_ctx.size().width - name.width()).max(0.0),
                     paint_ctx.size().height / 2.,
                 ),
             ));
 
-            paint_ctx.draw_text(&name, name_origin, &MAIN_COLOR);
+            name.draw(
+                paint_ctx,
+                name_origin,
+                &env.get(MAIN_COLOR),
+                &env.get(HIGHLIGHT_COLOR),
+            );
         }
         if let Some(description) = &self.description {
             let description_origin = UnitPoint::LEFT.resolve(Rect::from_origin_size(
-                Point::from((PADDING + 64., paint_ctx.size().height / 2.)),
+                Point::from((padding + 64., paint_ctx.size().height / 2.)),
                 Size::new(
                     (paint_ctx.size().width - description.width()).max(0.0),
                     paint_ctx.size().height / 2.,
                 ),
             ));
-            paint_ctx.draw_text(&description, description_origin, &SECONDARY_COLOR);
+            description.draw(
+                paint_ctx,
+                description_origin,
+                &env.get(SECONDARY_COLOR),
+                &env.get(HIGHLIGHT_COLOR),
+            );
         }
     }
 }
@@ -1,5 +1,16 @@
 mod autotextbox;
-pub use autotextbox::AutoTextBox;
+pub use autotextbox::{AutoTextBox, ACTIONS_REQUESTED, QUERY_CHANGED};
 
 mod listelement;
-pub use listelement::ListElement;
+pub use listelement::{ListElement, RESULTS_SCROLLED, RESULT_CLICKED};
+
+mod background;
+pub use background::Background;
+
+mod preview;
+pub use preview::PreviewPane;
+
+mod elided;
+pub use elided::ElidedLabel;
+
+mod editing;
@@ -0,0 +1,171 @@
+//! The pane right of the list, with more about the selected result.
+use druid::kurbo::{Line, Point, Rect, Size};
+use druid::piet::{
+    CairoFont, CairoTextLayout, FontBuilder, ImageFormat, InterpolationMode, PietText,
+    RenderContext, Text, TextLayout, TextLayoutBuilder,
+};
+use druid::{
+    theme, BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, UpdateCtx, Widget,
+};
+use std::sync::Arc;
+
+use crate::config::{
+    DESCRIPTION_FONT_SIZE, MAIN_COLOR, NAME_FONT_SIZE, PADDING, SECONDARY_COLOR, SELECTION_COLOR,
+};
+use crate::state::Preview;
+
+pub struct PreviewPane;
+
+impl PreviewPane {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Break text into lines no wider than `width`, at spaces. Words that
+/// are wider than that get a line of their own.
+fn wrap(
+    piet_text: &mut PietText,
+    font: &CairoFont,
+    text: &str,
+    width: f64,
+) -> Vec<CairoTextLayout> {
+    let mut layouts = Vec::new();
+    let mut layout = |text: &str| piet_text.new_text_layout(font, text).build().unwrap();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if !line.is_empty() && layout(&candidate).width() > width {
+                layouts.push(layout(&line));
+                line = word.to_string();
+            } else {
+                line = candidate;
+            }
+        }
+        layouts.push(layout(&line));
+    }
+    layouts
+}
+
+impl Widget<Arc<Preview>> for PreviewPane {
+    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut Arc<Preview>, _env: &Env) {
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &Arc<Preview>,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &Arc<Preview>,
+        data: &Arc<Preview>,
+        _env: &Env,
+    ) {
+        if !old_data.same(data) {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &Arc<Preview>,
+        _env: &Env,
+    ) -> Size {
+        bc.max()
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &Arc<Preview>, env: &Env) {
+        let size = paint_ctx.size();
+        let padding = env.get(PADDING);
+        let width = size.width - padding * 2.;
+        let font_name = env.get(theme::FONT_NAME);
+        let (main, secondary) = (env.get(MAIN_COLOR), env.get(SECONDARY_COLOR));
+
+        // Separate the pane from the list
+        paint_ctx.stroke(
+            Line::new((0.5, padding), (0.5, size.height - padding)),
+            &env.get(SELECTION_COLOR),
+            1.,
+        );
+
+        let text = paint_ctx.text();
+        let title_size = env.get(NAME_FONT_SIZE);
+        let text_size = env.get(DESCRIPTION_FONT_SIZE) * 0.8;
+        let title_font = text
+            .new_font_by_name(font_name, title_size)
+            .build()
+            .unwrap();
+        let font = text.new_font_by_name(font_name, text_size).build().unwrap();
+        let line_height = text_size * 1.3;
+
+        // Everything is laid out first, then drawn top to bottom
+        let mut lines: Vec<(CairoTextLayout, f64, bool)> = Vec::new();
+        let mut y = padding + title_size;
+        for layout in wrap(text, &title_font, &data.title, width) {
+            lines.push((layout, y, true));
+            y += title_size * 1.3;
+        }
+        y += line_height * 0.5;
+        for (label, value) in &data.fields {
+            let label = text.new_text_layout(&font, label).build().unwrap();
+            lines.push((label, y, false));
+            y += line_height;
+            for layout in wrap(text, &font, value, width) {
+                lines.push((layout, y, true));
+                y += line_height;
+            }
+        }
+        if !data.body.is_empty() {
+            y += line_height * 0.5;
+            for layout in wrap(text, &font, &data.body, width) {
+                lines.push((layout, y, true));
+                y += line_height;
+            }
+        }
+
+        let _ = paint_ctx.with_save(|rc| {
+            rc.clip(Rect::from_origin_size(Point::ORIGIN, size));
+            for (layout, y, is_main) in &lines {
+                let color = if *is_main { &main } else { &secondary };
+                rc.draw_text(layout, (padding, *y), color);
+            }
+            Ok(())
+        });
+
+        // The image goes below the text, scaled down to fit what's left
+        if let Some(im) = &data.image {
+            let top = y;
+            let available = Size::new(width, size.height - top - padding);
+            if available.height <= 0. {
+                return;
+            }
+            let scale = (available.width / im.width as f64)
+                .min(available.height / im.height as f64)
+                .min(1.);
+            let image_size = Size::new(im.width as f64 * scale, im.height as f64 * scale);
+            if let Ok(image) =
+                paint_ctx.make_image(im.width, im.height, &im.data, ImageFormat::RgbaSeparate)
+            {
+                paint_ctx.draw_image(
+                    &image,
+                    Rect::from_origin_size((padding, top), image_size),
+                    InterpolationMode::Bilinear,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Reading hosts from the ssh config and known_hosts fixtures.
+use std::fs;
+use std::path::PathBuf;
+
+use fuzzle::launch::{actions, launch_argvs, ResultAction};
+use fuzzle::providers::ssh::{config_hosts, known_hosts, Host};
+
+fn fixtures() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ssh")
+}
+
+fn names(hosts: &[Host]) -> Vec<&str> {
+    hosts.iter().map(|host| host.name.as_str()).collect()
+}
+
+#[test]
+fn config_hosts_follow_includes_and_skip_patterns() {
+    let dir = fixtures();
+    let hosts = config_hosts(&dir.join("config"), &dir);
+    assert_eq!(
+        names(&hosts),
+        vec!["web1", "web2", "db", "bastion", "pi", "ci", "gitlab"]
+    );
+}
+
+#[test]
+fn config_hosts_are_described_by_their_block() {
+    let dir = fixtures();
+    let hosts = config_hosts(&dir.join("config"), &dir);
+    let describe = |name: &str| {
+        let host = hosts.iter().find(|host| host.name == name).unwrap();
+        host.description.clone()
+    };
+    // `%h` in HostName is the name the host was found by
+    assert_eq!(describe("web1"), "SSH config · deploy@web1.example.com");
+    assert_eq!(describe("web2"), "SSH config · deploy@web2.example.com");
+    assert_eq!(describe("db"), "SSH config · 10.0.0.5");
+    // Quotes are removed, and the first value is the one that counts
+    assert_eq!(describe("bastion"), "SSH config · ops@bastion.example.com");
+    assert_eq!(describe("ci"), "SSH config · jenkins@");
+    assert_eq!(describe("gitlab"), "SSH config · gitlab.example.com");
+}
+
+#[test]
+fn includes_in_a_host_block_keep_it_going() {
+    let dir = fixtures().join("include-in-host");
+    let hosts = config_hosts(&dir.join("config"), &dir);
+    assert_eq!(names(&hosts), vec!["mirror", "archive"]);
+    assert_eq!(
+        hosts[0].description,
+        "SSH config · backup@mirror.example.com"
+    );
+    assert_eq!(hosts[1].description, "SSH config · archive.example.com");
+}
+
+#[test]
+fn missing_config_has_no_hosts() {
+    let dir = fixtures();
+    assert!(config_hosts(&dir.join("nothing-here"), &dir).is_empty());
+}
+
+#[test]
+fn known_hosts_skip_hashed_patterns_and_markers() {
+    let content = fs::read_to_string(fixtures().join("known_hosts")).unwrap();
+    let hosts = known_hosts(&content);
+    assert_eq!(
+        names(&hosts),
+        vec!["github.com", "140.82.121.4", "git.example.com:2222", "web1"]
+    );
+}
+
+#[test]
+fn known_hosts_on_other_ports_are_urls() {
+    let hosts = known_hosts("[git.example.com]:2222 ssh-ed25519 AAAA\n");
+    assert_eq!(hosts[0].destination, "ssh://git.example.com:2222");
+}
+
+#[test]
+fn host_actions_run_ssh_in_one_terminal() {
+    let host = known_hosts("[git.example.com]:2222 ssh-ed25519 AAAA\n").remove(0);
+    let result = host.result();
+    assert_eq!(
+        actions(&result),
+        vec![ResultAction::Launch, ResultAction::CopyCommand]
+    );
+    // Launch
+    assert_eq!(
+        launch_argvs("xterm -e", &result, &[]),
+        vec![vec!["xterm", "-e", "ssh", "ssh://git.example.com:2222"]]
+    );
+    // Copy the command
+    assert_eq!(result.command, "ssh \"ssh://git.example.com:2222\"");
+}